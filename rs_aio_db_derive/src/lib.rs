@@ -0,0 +1,178 @@
+// The crate writes explicit returns, like rs_aio_db
+#![allow(clippy::needless_return)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, ExprLit, ExprUnary, Fields, Lit, LitInt, LitStr, UnOp};
+
+/// Implements `rs_aio_db::AioModel` from the `#[aio(...)]` attributes of a struct.
+///
+/// - `#[aio(table = "...")]` on the struct overrides the table name
+/// - `#[aio(version = N)]` on the struct sets the model version, used by `Migrator::upgrade`
+/// - `#[aio(primary_key)]`, `#[aio(auto_increment)]`, `#[aio(unique)]` and `#[aio(index)]` on a field
+/// - `#[aio(rename = "...")]` stores the field in another column
+/// - `#[aio(skip)]` doesn't store the field, it's left to its default value when read
+/// - `#[aio(default = ...)]` sets the `DEFAULT` of the column, a string, integer, float or bool literal
+/// - `#[aio(renamed_from = "...")]` renames the former column of the field instead of adding a new one, can be repeated
+#[proc_macro_derive(AioModel, attributes(aio))]
+pub fn derive_aio_model(input: TokenStream) -> TokenStream {
+     let input = parse_macro_input!(input as DeriveInput);
+
+     return match expand(&input) {
+          Ok(tokens) => tokens.into(),
+          Err(error) => error.to_compile_error().into()
+     };
+}
+
+#[derive(Default)]
+struct StructAttributes {
+     table_name: Option<String>,
+     version: u32
+}
+
+#[derive(Default)]
+struct FieldAttributes {
+     column_name: Option<String>,
+     primary_key: bool,
+     auto_increment: bool,
+     unique: bool,
+     index: bool,
+     skip: bool,
+     default: Option<String>,
+     renamed_from: Vec<String>
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, syn::Error> {
+     let Data::Struct(data) = &input.data else {
+          return Err(syn::Error::new_spanned(&input.ident, "AioModel can only be derived for structs."));
+     };
+
+     let Fields::Named(fields) = &data.fields else {
+          return Err(syn::Error::new_spanned(&input.ident, "AioModel can only be derived for structs with named fields."));
+     };
+
+     let StructAttributes { table_name, version } = parse_struct_attributes(input)?;
+
+     let table_name = match table_name {
+          Some(table_name) => quote! { Some(#table_name.to_string()) },
+          None => quote! { None }
+     };
+
+     let mut field_infos = Vec::with_capacity(fields.named.len());
+
+     for field in fields.named.iter() {
+          let field_name = field.ident.as_ref().unwrap().to_string();
+          let attributes = parse_field_attributes(field)?;
+
+          if attributes.skip && (attributes.primary_key || attributes.auto_increment || attributes.unique || attributes.index || !attributes.renamed_from.is_empty()) {
+               return Err(syn::Error::new_spanned(field, "A skipped field can't be a key, indexed or renamed."));
+          }
+
+          let column_name = option_tokens(attributes.column_name);
+          let default = option_tokens(attributes.default);
+          let FieldAttributes { primary_key, auto_increment, unique, index, skip, renamed_from, .. } = attributes;
+
+          field_infos.push(quote! {
+               ::rs_aio_db::db::models::FieldInfo {
+                    field_name: #field_name.to_string(),
+                    column_name: #column_name,
+                    primary_key: #primary_key,
+                    auto_increment: #auto_increment,
+                    unique: #unique,
+                    index: #index,
+                    skip: #skip,
+                    default: #default,
+                    renamed_from: vec![#(#renamed_from.to_string()),*]
+               }
+          });
+     }
+
+     let ident = &input.ident;
+     let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+     return Ok(quote! {
+          impl #impl_generics ::rs_aio_db::db::models::AioModel for #ident #type_generics #where_clause {
+               fn model_info() -> ::rs_aio_db::db::models::ModelInfo {
+                    return ::rs_aio_db::db::models::ModelInfo {
+                         table_name: #table_name,
+                         version: #version,
+                         fields: vec![#(#field_infos),*]
+                    };
+               }
+          }
+     });
+}
+
+fn parse_struct_attributes(input: &DeriveInput) -> Result<StructAttributes, syn::Error> {
+     let mut attributes = StructAttributes::default();
+
+     for attribute in input.attrs.iter().filter(|x| x.path().is_ident("aio")) {
+          attribute.parse_nested_meta(|meta| {
+               if meta.path.is_ident("table") {
+                    attributes.table_name = Some(meta.value()?.parse::<LitStr>()?.value());
+               } else if meta.path.is_ident("version") {
+                    attributes.version = meta.value()?.parse::<LitInt>()?.base10_parse::<u32>()?;
+               } else {
+                    return Err(meta.error("Unsupported aio attribute, expected `table` or `version`."));
+               }
+
+               return Ok(());
+          })?;
+     }
+
+     return Ok(attributes);
+}
+
+fn parse_field_attributes(field: &syn::Field) -> Result<FieldAttributes, syn::Error> {
+     let mut attributes = FieldAttributes::default();
+
+     for attribute in field.attrs.iter().filter(|x| x.path().is_ident("aio")) {
+          attribute.parse_nested_meta(|meta| {
+               if meta.path.is_ident("primary_key") {
+                    attributes.primary_key = true;
+               } else if meta.path.is_ident("auto_increment") {
+                    attributes.auto_increment = true;
+               } else if meta.path.is_ident("unique") {
+                    attributes.unique = true;
+               } else if meta.path.is_ident("index") {
+                    attributes.index = true;
+               } else if meta.path.is_ident("skip") {
+                    attributes.skip = true;
+               } else if meta.path.is_ident("rename") {
+                    attributes.column_name = Some(meta.value()?.parse::<LitStr>()?.value());
+               } else if meta.path.is_ident("renamed_from") {
+                    attributes.renamed_from.push(meta.value()?.parse::<LitStr>()?.value());
+               } else if meta.path.is_ident("default") {
+                    let expr = meta.value()?.parse::<Expr>()?;
+                    attributes.default = Some(get_sql_literal(&expr)?);
+               } else {
+                    return Err(meta.error("Unsupported aio attribute, expected one of `primary_key`, `auto_increment`, `unique`, `index`, `skip`, `rename`, `default` or `renamed_from`."));
+               }
+
+               return Ok(());
+          })?;
+     }
+
+     return Ok(attributes);
+}
+
+/// Converts the literal of `#[aio(default = ...)]` to SQL, e.g. `"it's"` to `'it''s'` and `true` to `1`.
+fn get_sql_literal(expr: &Expr) -> Result<String, syn::Error> {
+     return match expr {
+          Expr::Lit(ExprLit { lit: Lit::Str(value), .. }) => Ok(format!("'{}'", value.value().replace('\'', "''"))),
+          Expr::Lit(ExprLit { lit: Lit::Char(value), .. }) => Ok(format!("'{}'", value.value().to_string().replace('\'', "''"))),
+          Expr::Lit(ExprLit { lit: Lit::Int(value), .. }) => Ok(value.base10_digits().to_string()),
+          Expr::Lit(ExprLit { lit: Lit::Float(value), .. }) => Ok(value.base10_digits().to_string()),
+          Expr::Lit(ExprLit { lit: Lit::Bool(value), .. }) => Ok(if value.value { "1".into() } else { "0".into() }),
+          Expr::Unary(ExprUnary { op: UnOp::Neg(_), expr: inner, .. }) if matches!(&**inner, Expr::Lit(ExprLit { lit: Lit::Int(_) | Lit::Float(_), .. })) => Ok(format!("-{}", get_sql_literal(inner)?)),
+          _ => Err(syn::Error::new_spanned(expr, "The default must be a string, char, integer, float or bool literal."))
+     };
+}
+
+fn option_tokens(value: Option<String>) -> TokenStream2 {
+     return match value {
+          Some(value) => quote! { Some(#value.to_string()) },
+          None => quote! { None }
+     };
+}
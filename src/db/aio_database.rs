@@ -1,500 +1,942 @@
-use std::fs::create_dir;
-
-use bevy_reflect::Struct;
-use log::debug;
-use log::info;
-use serde::Deserialize;
-use serde::Serialize;
-use tokio_rusqlite::Connection as SqliteConnection;
-
-use crate::db::internal::helpers::get_system_char_delimiter;
-use crate::db::internal::queries::alter_table_drop_column;
-use crate::db::internal::queries::alter_table_new_column;
-
-use super::WalMode;
-use super::aio_query::AnyCountResult;
-use super::aio_query::QueryBuilder;
-use super::aio_query::QueryRowResult;
-use super::aio_query::QueryRowsResult;
-use super::internal::helpers::get_schema_from_generic;
-use super::internal::queries::set_wal_mode;
-use super::internal::queries::all_query;
-use super::internal::queries::any_count_query;
-use super::internal::queries::change_db_settings;
-use super::internal::queries::change_synchronous_settings;
-use super::internal::queries::create_index;
-use super::internal::queries::create_unique_index;
-use super::internal::queries::create_table;
-use super::internal::queries::delete_value;
-use super::internal::queries::drop_index;
-use super::internal::queries::get_current_db_schema;
-use super::internal::queries::get_many_values;
-use super::internal::queries::get_single_value;
-use super::internal::queries::insert_value;
-use super::internal::queries::partial_update;
-use super::internal::queries::set_wal_mode_to_rollback;
-use super::internal::queries::update_value;
-use super::models::Schema;
-
-/// # All in one aka Aio Database
-/// ### Locally preserved database example
-/// ```rust
-/// //This will create a Test.db file at G:\ location
-/// let file_db = AioDatabase::create::<Person>("G:\\".into(), "Test".into()).await;
-/// ```
-/// ### In-memory database example
-/// ```rust
-/// let in_memory_db = AioDatabase::create_in_memory::<Person>("Test".into()).await;
-/// ```
-/// #### Create a model
-/// ```rust
-/// use rs_aio_db::Reflect;
-/// 
-/// #[derive(Default, Clone, Debug, Reflect)]
-/// struct Person {
-///     name: String,
-///     age: i32,
-///     height: i32,
-///     married: bool,
-/// }
-/// ```
-/// 
-/// #### For Inserting values:
-/// ```rust
-/// file_db.insert_value(Person {
-///    name: "Mylo".into(),
-///    age: 0,
-///    height: 0,
-///    married: true
-/// }).await;
-/// ```
-/// 
-/// #### For getting existing values / records:
-/// ```rust
-/// let get_record = file_db
-///    .query()
-///    .field("age")
-///    .where_is(Operator::Gt(5.to_string()), Some(Next::Or))
-///    .field("name")
-///    .where_is(Operator::Eq("Mylo".into()), None)
-///    .get_many_values::<Person>().await;
-/// ```
-/// 
-/// #### Update existing values / records:
-/// ```rust
-/// let update_rows = file_db
-///    .query()
-///    .field("age")
-///    .where_is(Operator::Eq((0).to_string()), Some(Next::Or))
-///    .update_value(Person {
-///        name: "Mylo".into(),
-///        age: 5,
-///        height: 5,
-///        married: false
-///    }).await;
-/// ```
-/// 
-/// #### Deleting existing values / records:
-/// ```rust
-/// let delete_rows = file_db
-///    .query()
-///    .field("name")
-///    .where_is(Operator::Eq("Mylo".into()), None)
-///    .delete_value::<Person>().await;
-/// ```
-pub struct AioDatabase {
-     name: String,
-     conn: AioDatabaseConnection,
-     schema: Box<Vec<Schema>>,
-     retries: u32
-}
-unsafe impl Send for AioDatabase {}
-unsafe impl Sync for AioDatabase {}
-
-pub(crate) struct AioDatabaseConnection {
-     sqlite_connection: SqliteConnection
-}
-
-unsafe impl Send for AioDatabaseConnection {}
-unsafe impl Sync for AioDatabaseConnection {}
-
-impl AioDatabase {
-     /// Create a locally persisted database. Recommended to run `set_wal_mode` to WAL2 mode after creation.
-     pub async fn create<'a, T>(location: String, name: String) -> AioDatabase  where T: Default + Struct + Clone + Send + Send {       
-          let system_char_delimiter = get_system_char_delimiter();
-
-          _ = create_dir(location.clone());
-          
-          let db_location = if location.ends_with(system_char_delimiter) {
-               format!("{}{}{}", location, name, ".db")
-          } else {
-               format!("{}{}{}{}", location, get_system_char_delimiter(), name, ".db")
-          };
-
-          let sqlite_connection = tokio_rusqlite::Connection::open(db_location).await.expect("Error opening a connection to this file.");
-
-          let aio_conn = AioDatabaseConnection {
-               sqlite_connection: sqlite_connection
-          };
-
-          let generic_schema = get_schema_from_generic::<T>();
-          let current_schema_option = get_current_db_schema(&name, &aio_conn.sqlite_connection).await;
-
-          if let Some(current_schema) = current_schema_option {
-               debug!("Current Db schema: {:?}", current_schema);
-
-               for current in current_schema.iter() {
-                    if !generic_schema.iter().any(|x| x.field_name == current.field_name) {
-                         info!("Dropping column: {}", current.field_name.as_str());
-                         alter_table_drop_column(&name, current.field_name.as_str(), &aio_conn.sqlite_connection).await;
-                         continue;
-                    }
-               }
-
-               for generic_field in generic_schema.iter() {
-                    if !current_schema.iter().any(|x| x.field_name == generic_field.field_name) {
-                         info!("Adding column: {} as {}", generic_field.field_name.as_str(), generic_field.field_type.as_str());
-                         alter_table_new_column(&name, generic_field, &aio_conn.sqlite_connection).await;
-                         continue;
-                    }
-               }
-          }
-          else {
-               debug!("Creating table {} with schema: {:?}", name, generic_schema);
-               change_db_settings(&aio_conn.sqlite_connection).await;
-               create_table(&generic_schema, &name, &aio_conn.sqlite_connection).await;
-          }
-
-          let db = AioDatabase {
-               name: name,
-               conn: aio_conn,
-               schema: generic_schema,
-               retries: 5
-          };
-
-          return db;
-     }
-
-     /// Create an in-memory database.
-     pub async fn create_in_memory<'a, T: Default + Struct + Clone + Send + Send>(name: String) -> AioDatabase {
-          let sqlite_connection = tokio_rusqlite::Connection::open(":memory:").await.expect("Error opening a in-memory database.");
-
-          let aio_conn = AioDatabaseConnection {
-               sqlite_connection: sqlite_connection
-          };
-
-          let generic_schema = get_schema_from_generic::<T>();
-          let current_schema_option = get_current_db_schema(&name, &aio_conn.sqlite_connection).await;
-
-          if let Some(current_schema) = current_schema_option {
-               debug!("Current Db schema: {:?}", current_schema);
-
-               for current in current_schema.iter() {
-                    if !generic_schema.iter().any(|x| x.field_name == current.field_name) {
-                         info!("Dropping column: {}", current.field_name.as_str());
-                         alter_table_drop_column(&name, current.field_name.as_str(), &aio_conn.sqlite_connection).await;
-                         continue;
-                    }
-               }
-
-               for generic_field in generic_schema.iter() {
-                    if !current_schema.iter().any(|x| x.field_name == generic_field.field_name) {
-                         info!("Adding column: {} as {}", generic_field.field_name.as_str(), generic_field.field_type.as_str());
-                         alter_table_new_column(&name, generic_field,  &aio_conn.sqlite_connection).await;
-                         continue;
-                    }
-               }
-          }
-          else {
-               debug!("Creating table {} with schema: {:?}", name, generic_schema);
-               change_db_settings( &aio_conn.sqlite_connection).await;
-               create_table(&generic_schema, &name, &aio_conn.sqlite_connection).await;
-          }
-          
-          let db = AioDatabase {
-               name: name,
-               conn: aio_conn,
-               schema: generic_schema,
-               retries: 5
-          };
-
-          return db;
-     }
-
-     /// Set `journal_mode` between WAL or WAL2. 
-     pub async fn set_wal_mode(&self, wal_mode: WalMode) -> Result<(), String> {
-
-          return set_wal_mode(&self.conn.sqlite_connection, wal_mode).await;
-     }
-
-     /// Set `journal_mode` to `delete` in order to be compatible to older SQLite versions or to change the mode to WAL2 from WAL.
-     pub  async fn set_wal_mode_to_rollback(&self) {
-          
-          _ = set_wal_mode_to_rollback(&self.conn.sqlite_connection).await;
-     }
-
-     /// Sets how many retries should be made if a query fails. The delay between retries is 10ms.
-     pub fn set_query_retries(&mut self, retries: u32) {
-          self.retries = retries;
-     }
-
-     /// Get the name of the database and table's name as well.
-     pub fn get_name(&self) -> &str {
-          return self.name.as_str();
-     }
-
-     /// Get the schema of the struct / database.
-     pub fn get_schema(&self) -> &Vec<Schema> {
-          return &self.schema;
-     }
-
-     /// If set_synchronous(true) then the PRAGMA synchronous will equal to NORMAL (recommended) or false for PRAGMA synchronous to equal to OFF. 
-     /// That way transaction will be allowed to be asynchronous which may increase performance but in case of an accident the DB may be corrupted.
-     pub async fn set_synchronous(&self, val: bool) {
-          
-          change_synchronous_settings(&self.conn.sqlite_connection, val).await;
-     }
-
-     /// Inserts a **T** value in the database. Returns if the insertion was successful or not after certain retries.
-     pub async fn insert_value<'a, T: Default + Struct + Clone + Send>(&self, value: &T) -> Result<(), String> {
-          
-          let result = insert_value::<T>(&value, self.get_name(), &self.conn.sqlite_connection, self.retries, false).await;
-          if let Ok(result) = result {
-               return Ok(result);
-          }
-          else {
-               return Err(
-                    format!("Insert query retried {} times, but still failed. Increase retry count or lower the concurrent writes to database.", self.retries)
-               );
-          }
-     }
-
-     /// Inserts a **T** value in the database concurrently. Returns if the insertion was successful or not after certain retries.
-     pub(crate) async fn _insert_value_concurrent<'a, T: Default + Struct + Clone + Send>(&self, value: &T) -> Result<(), String> {
-          
-          let result = insert_value::<T>(&value, self.get_name(), &self.conn.sqlite_connection, self.retries, true).await;
-          if let Ok(result) = result {
-               return Ok(result);
-          }
-          else {
-               return Err(
-                    format!("Insert query retried {} times, but still failed. Increase retry count or lower the concurrent writes to database.", self.retries)
-               );
-          }
-     }
-
-     /// Creates a QueryBuilder that allows to chain query filters for different field / columns.
-     pub fn query<'a>(&'a self) -> QueryBuilder<'a> {
-          return QueryBuilder {
-               table_name: self.get_name().to_string(),
-               query_options: Vec::default(),
-               db: &self
-          }
-     }
-     
-     pub(crate) async fn get_single_value<'a, T: Default + Struct + Clone + Send>(&self, query_string: String) -> Option<T> {
-          
-          if let Some(mut query_result) = QueryRowResult::<T>::new(query_string, &self.conn.sqlite_connection).await {
-               get_single_value::<T>(&mut query_result);
-               return query_result.value;
-          }
-          else {
-               return None;
-          }
-     }
-
-     pub(crate) async fn get_many_values<T: Default + Struct + Clone + Send>(&self, query_string: String) -> Option<Vec<T>> {
-          
-          if let Some(query_result) = QueryRowsResult::<T>::new_many(query_string, &self.conn.sqlite_connection).await {
-               let result = get_many_values::<T>(query_result).await;
-               return result;
-          }
-          else {
-               return None;
-          }
-     }
-
-     pub(crate) async fn update_value<'a, T: Default + Struct + Clone + Send>(&self, value: T, where_query: String) -> Result<usize, String> {
-          
-          let result = update_value::<T>(&value, self.get_name(), &where_query, &self.conn.sqlite_connection, self.retries, false).await;
-          if let Ok(result) = result {
-               return Ok(result);
-          }
-          else {
-               return Err(
-                    format!("Update query retried {} times, but still failed. Increase retry count or lower the concurrent writes to database.", self.retries)
-               );
-          }
-     }
-
-     pub(crate) async fn update_value_concurrent<'a, T: Default + Struct + Clone + Send>(&self, value: T, where_query: String) -> Result<usize, String> {
-          
-          let result = update_value::<T>(&value, self.get_name(), &where_query, &self.conn.sqlite_connection, self.retries, true).await;
-          if let Ok(result) = result {
-               return Ok(result);
-          }
-          else {
-               return Err(
-                    format!("Update query retried {} times, but still failed. Increase retry count or lower the concurrent writes to database.", self.retries)
-               );
-          }
-     }
-
-     pub(crate) async fn partial_update<'a, T: Default + Struct + Clone + Send>(&self, field_name: String, field_value: String, where_query: String) ->  Result<usize, String> {
-          
-          let result = partial_update::<T>(field_name, field_value, self.get_name(), &where_query, &self.conn.sqlite_connection, self.retries, false).await;
-          if let Ok(result) = result {
-               return Ok(result);
-          }
-          else {
-               return Err(
-                    format!("Partial update query retried {} times, but still failed. Increase retry count or lower the concurrent writes to database.", self.retries)
-               );
-          }
-     }
-
-     pub(crate) async fn partial_update_concurrent<'a, T: Default + Struct + Clone + Send>(&self, field_name: String, field_value: String, where_query: String) ->  Result<usize, String> {
-          
-          let result = partial_update::<T>(field_name, field_value, self.get_name(), &where_query, &self.conn.sqlite_connection, self.retries, true).await;
-          if let Ok(result) = result {
-               return Ok(result);
-          }
-          else {
-               return Err(
-                    format!("Partial update query retried {} times, but still failed. Increase retry count or lower the concurrent writes to database.", self.retries)
-               );
-          }
-     }
-
-     pub(crate) async fn delete_value<'a, T: Default + Struct + Clone + Send>(&self, where_query: String) -> Result<usize, String> {
-          
-          let result = delete_value::<T>(self.get_name(), &where_query, &self.conn.sqlite_connection, self.retries).await;
-          if let Ok(result) = result {
-               return Ok(result);
-          }
-          else {
-               return Err(
-                    format!("Delete query retried {} times, but still failed. Increase retry count or lower the concurrent writes to database.", self.retries)
-               );
-          }
-     }
-
-     pub(crate) async fn any<'a, T: Default + Struct + Clone + Send>(&self, where_query: String) -> bool {
-          let query = any_count_query::<T>(self.get_name(), &where_query).await;
-          
-          if let Some(mut query_result) = QueryRowResult::<AnyCountResult>::new(query, &self.conn.sqlite_connection).await {
-               get_single_value::<AnyCountResult>(&mut query_result);
-               if let Some(any_result) = query_result.value {
-                    return match any_result.count_total {
-                         0 => false,
-                         1.. => true
-                    };
-               }
-               else {
-                   return false;
-               }
-          }
-          else {
-               return false;
-          }
-     }
-
-     pub(crate) async fn count<'a, T: Default + Struct + Clone + Send>(&self, where_query: String) -> u64 {
-          let query = any_count_query::<T>(self.get_name(), &where_query).await;
-          
-          if let Some(mut query_result) = QueryRowResult::<AnyCountResult>::new(query, &self.conn.sqlite_connection).await {
-               get_single_value::<AnyCountResult>(&mut query_result);
-               if let Some(any_result) = query_result.value {
-                    return any_result.count_total;
-               }
-               else {
-                   return 0;
-               }
-          }
-          else {
-               return 0;
-          }
-     }
-
-     pub(crate) async fn all<'a, T: Default + Struct + Clone + Send>(&self, where_query: String) -> bool {
-          let all_query = all_query::<T>(self.get_name()).await;
-          let any_query = any_count_query::<T>(self.get_name(), &where_query).await;
-          
-          if let Some(mut query_result) = QueryRowResult::<AnyCountResult>::new(all_query, &self.conn.sqlite_connection).await {
-               get_single_value::<AnyCountResult>(&mut query_result);
-               if let Some(all_result) = query_result.value.clone() {
-                    let all_records = all_result.count_total.clone();
-                    
-                    drop(all_result);
-                    drop(query_result);
-
-                    if let Some(mut query_result) = QueryRowResult::<AnyCountResult>::new(any_query, &self.conn.sqlite_connection).await {
-                         get_single_value::<AnyCountResult>(&mut query_result);
-                         if let Some(any_result) = query_result.value {
-
-                              return any_result.count_total == all_records;
-                         }
-                         else {
-                             return true;
-                         }
-                    }
-                    else {
-                         return true;
-                    }
-               }
-               else {
-                   return true;
-               }
-          }
-          else {
-               return true;
-          }
-     }
-
-     /// Create a non-unique index for a set of columns / struct fields if doesn't exist. Might lead to better performance.
-     pub async fn create_index<'a, T: Default + Struct + Clone + Send> (
-          &self,
-          index_name: &str,
-          columns: Vec<String>) -> Result<(), String> {
-          let query = create_index::<T>(index_name, &self.name, columns);
-
-          _ = self.conn.sqlite_connection.call(move |conn| { Ok(conn.execute(&query, ())) });
-
-          Ok(())
-     }
-
-     /// Create a unique index for a set of columns / struct fields if doesn't exist. Might lead to better performance.
-     pub async fn create_unique_index<'a, T: Default + Struct + Clone + Send> (
-          &self,
-          index_name: &str,
-          columns: Vec<String>) -> Result<(), String> {
-          let query = create_unique_index::<T>(index_name, &self.name, columns);
-          
-
-          _ = self.conn.sqlite_connection.call(move |conn| { Ok(conn.execute(&query, ())) });
-
-          Ok(())
-     }
-
-     /// Drop an index if exists.
-     pub async fn drop_index(
-          &self,
-          index_name: &str) -> Result<(), String> {
-          let query = drop_index(index_name);
-          
-
-          _ = self.conn.sqlite_connection.call(move |conn| { Ok(conn.execute(&query, ())) });
-
-          Ok(())
-     }
-
-     pub fn get_bytes<'a, S: Serialize + Deserialize<'a>>(struct_to_bytes: S) -> Vec<u8> {
-          let bytes = bincode::serialize(&struct_to_bytes).unwrap();
-          return bytes;
-     }
-
-     pub fn get_struct<'a, S: Serialize + Deserialize<'a>>(vec_u8_to_struct: &'a Vec<u8>) -> S {
-          let bytes = bincode::deserialize(vec_u8_to_struct).unwrap();
-          return bytes;
-     }
-}
\ No newline at end of file
+use std::any::TypeId;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use rusqlite::ToSql;
+use rusqlite::types::Value;
+use tokio::sync::Mutex;
+use tokio::sync::OwnedMutexGuard;
+
+use bevy_reflect::Struct;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio_rusqlite::Connection as SqliteConnection;
+
+use super::{JournalMode, WalMode};
+use super::aio_error::AioError;
+use super::aio_migration::Migrator;
+use super::aio_options::AioDatabaseOptions;
+use super::aio_pragmas::Pragmas;
+use super::aio_table::AioTable;
+use super::aio_transaction::AioTransaction;
+use super::aio_transaction::OpenSavepoint;
+use super::aio_transaction::TransactionMode;
+use super::aio_query::AnyCountResult;
+use super::aio_query::QueryBuilder;
+use super::aio_query::OwnedQueryBuilder;
+use super::aio_query::QueryRowResult;
+use super::aio_query::QueryRowsResult;
+use super::internal::helpers::get_schema_from_generic;
+use super::internal::helpers::to_sql_value;
+use super::internal::migrations::backfill_columns;
+use super::internal::migrations::check_backfills;
+use super::internal::migrations::migrate;
+use super::internal::migrations::pending_steps;
+use super::internal::migrations::read_migration_history;
+use super::internal::migrations::revert_migration_steps;
+use super::internal::migrations::run_migration_steps;
+use super::internal::migrations::upgrade_table;
+use super::internal::migrations::write_model_version;
+use super::internal::queries::set_journal_mode;
+use super::internal::queries::all_query;
+use super::internal::queries::any_count_query;
+use super::internal::queries::change_synchronous_settings;
+use super::internal::pool::{ReadConnection, ReaderPool, ReaderSettings, WaitCounter};
+use super::internal::queries::create_index;
+use super::internal::queries::delete_by_id;
+use super::internal::queries::describe_table;
+use super::internal::queries::delete_value;
+use super::internal::queries::drop_index;
+use super::internal::queries::find_schema_field;
+use super::internal::queries::get_journal_mode;
+use super::internal::queries::get_pragma;
+use super::internal::queries::insert_many;
+use super::internal::queries::insert_value;
+use super::internal::queries::partial_update;
+use super::internal::queries::update_by_id;
+use super::internal::queries::update_value;
+use super::internal::queries::upsert_value;
+use super::models::AioModel;
+use super::models::AppliedMigration;
+use super::models::BoundQuery;
+use super::models::BulkInsertMode;
+use super::models::DatabaseSettings;
+use super::models::InsertManyResult;
+use super::models::MigrationPolicy;
+use super::models::MigrationReport;
+use super::models::PoolStats;
+use super::models::RetryPolicy;
+use super::models::Schema;
+use super::models::StatementCacheStats;
+use super::models::TableDescription;
+use super::models::UpsertAction;
+use super::models::UpsertResult;
+use super::internal::statements::DEFAULT_STATEMENT_CACHE_CAPACITY;
+use super::internal::statements::ModelStatements;
+use super::internal::statements::StatementCache;
+
+/// # All in one aka Aio Database
+/// ### Locally preserved database example
+/// ```rust
+/// //This will create a Test.db file at G:\ location
+/// let file_db = AioDatabase::create::<Person>("G:\\".into(), "Test".into()).await?;
+/// ```
+/// ### In-memory database example
+/// ```rust
+/// let in_memory_db = AioDatabase::create_in_memory::<Person>("Test".into()).await?;
+/// ```
+/// #### Create a model
+/// ```rust
+/// use rs_aio_db::{AioModel, Reflect};
+/// 
+/// #[derive(Default, Clone, Debug, Reflect, AioModel)]
+/// struct Person {
+///     name: String,
+///     age: i32,
+///     height: i32,
+///     married: bool,
+/// }
+/// ```
+/// 
+/// #### For Inserting values:
+/// ```rust
+/// file_db.insert_value(&Person {
+///    name: "Mylo".into(),
+///    age: 0,
+///    height: 0,
+///    married: true
+/// }).await?;
+/// ```
+/// 
+/// #### For getting existing values / records:
+/// ```rust
+/// let get_record = file_db
+///    .query()
+///    .field("age")
+///    .where_is(Operator::Gt(5.to_string()), Some(Next::Or))
+///    .field("name")
+///    .where_is(Operator::Eq("Mylo".into()), None)
+///    .get_many_values::<Person>().await?;
+/// ```
+/// 
+/// #### Update existing values / records:
+/// ```rust
+/// let update_rows = file_db
+///    .query()
+///    .field("age")
+///    .where_is(Operator::Eq((0).to_string()), Some(Next::Or))
+///    .update_value(Person {
+///        name: "Mylo".into(),
+///        age: 5,
+///        height: 5,
+///        married: false
+///    }).await?;
+/// ```
+/// 
+/// #### Deleting existing values / records:
+/// ```rust
+/// let delete_rows = file_db
+///    .query()
+///    .field("name")
+///    .where_is(Operator::Eq("Mylo".into()), None)
+///    .delete_value::<Person>().await?;
+/// ```
+/// 
+/// #### Sharing the database between tasks:
+/// Clones are cheap and share the connection, the statement cache and the registered models.
+/// ```rust
+/// let db = file_db.clone();
+/// tokio::spawn(async move { db.insert_value(&person).await });
+/// ```
+#[derive(Clone)]
+pub struct AioDatabase {
+     /// Shared by the clones of the database and the handles returned by `table`.
+     pub(crate) conn: Arc<AioDatabaseConnection>,
+     /// The table of the model the database was created for or selected with `table`, None for a database returned by `open`.
+     table: Option<Arc<ModelTable>>,
+     retry_policy: RetryPolicy
+}
+
+/// The writer connection, and the read-only connections of a file database in WAL mode.
+pub(crate) struct AioDatabaseConnection {
+     pub(crate) sqlite_connection: SqliteConnection,
+     pub(crate) statement_cache: Arc<StatementCache>,
+     state: Arc<Mutex<ConnectionState>>,
+     /// The tables of the registered models, shared by every handle of the connection.
+     tables: RwLock<HashMap<TypeId, Arc<ModelTable>>>,
+     /// The path or URI the readers open the database file with, None for an in-memory database.
+     path: Option<PathBuf>,
+     /// None if reads run on the writer.
+     readers: RwLock<Option<Arc<ReaderPool>>>,
+     reader_pool_size: AtomicUsize,
+     /// The settings the readers share with the writer, see `Pragmas`.
+     pub(crate) reader_settings: RwLock<ReaderSettings>,
+     reader_waits: WaitCounter,
+     writer_waits: WaitCounter
+}
+
+/// The table of a model: its name, columns and the statements generated for it.
+pub(crate) struct ModelTable {
+     pub(crate) name: String,
+     pub(crate) type_id: TypeId,
+     pub(crate) schema: Vec<Schema>,
+     pub(crate) statements: ModelStatements
+}
+
+/// Guarded by the connection lock, which every call holds while it runs and a transaction holds until it's finished.
+#[derive(Default)]
+pub(crate) struct ConnectionState {
+     /// A transaction was dropped without being finished and has to be rolled back before the connection is used again.
+     pub(crate) pending_rollback: bool
+}
+
+impl AioDatabaseConnection {
+     pub(crate) fn new(sqlite_connection: SqliteConnection, path: Option<PathBuf>, reader_pool_size: usize, reader_settings: ReaderSettings) -> AioDatabaseConnection {
+          return AioDatabaseConnection {
+               sqlite_connection: sqlite_connection,
+               statement_cache: Arc::new(StatementCache::new(DEFAULT_STATEMENT_CACHE_CAPACITY)),
+               state: Arc::new(Mutex::new(ConnectionState::default())),
+               tables: RwLock::new(HashMap::new()),
+               path: path,
+               readers: RwLock::new(None),
+               reader_pool_size: AtomicUsize::new(reader_pool_size),
+               reader_settings: RwLock::new(reader_settings),
+               reader_waits: WaitCounter::default(),
+               writer_waits: WaitCounter::default()
+          };
+     }
+
+     /// Waits until no call or transaction is using the connection, then reserves it for the caller.
+     pub(crate) async fn lock(&self) -> Result<OwnedMutexGuard<ConnectionState>, AioError> {
+          let started = Instant::now();
+          let mut guard = self.state.clone().lock_owned().await;
+          self.writer_waits.record(started);
+
+          if guard.pending_rollback {
+               self.sqlite_connection.call(|conn| {
+                    if !conn.is_autocommit() {
+                         conn.execute_batch("ROLLBACK")?;
+                    }
+                    Ok(())
+               }).await?;
+
+               guard.pending_rollback = false;
+          }
+
+          return Ok(guard);
+     }
+
+     /// Replaces the readers with `reader_pool_size` new ones if the database is a file in WAL mode and NORMAL locking mode,
+     /// otherwise reads run on the writer.
+     pub(crate) async fn open_readers(&self) -> Result<(), AioError> {
+          let size = self.reader_pool_size.load(Ordering::Relaxed);
+
+          self.close_readers().await?;
+
+          let pool = match &self.path {
+               Some(path) if size > 0 && get_journal_mode(&self.sqlite_connection).await? == JournalMode::Wal
+                    && get_pragma::<String>(&self.sqlite_connection, "locking_mode").await?.eq_ignore_ascii_case("normal") => {
+                    let capacity = self.statement_cache.stats().capacity;
+                    let settings = *self.reader_settings.read().unwrap();
+                    Some(Arc::new(ReaderPool::open(path, size, capacity, settings).await?))
+               },
+               _ => None
+          };
+
+          *self.readers.write().unwrap() = pool;
+
+          return Ok(());
+     }
+
+     /// Closes the readers, reads run on the writer until `open_readers` is called.
+     pub(crate) async fn close_readers(&self) -> Result<(), AioError> {
+          let current = self.readers.write().unwrap().take();
+
+          if let Some(current) = current {
+               current.close().await?;
+          }
+
+          return Ok(());
+     }
+
+     pub(crate) fn reader_pool(&self) -> Option<Arc<ReaderPool>> {
+          return self.readers.read().unwrap().clone();
+     }
+}
+
+impl AioDatabase {
+     /// Create a locally persisted database. It opens in WAL mode, see `set_journal_mode`.
+     /// 
+     /// An existing table is migrated with `MigrationPolicy::AdditiveOnly`, columns of removed fields are kept.
+     pub async fn create<T>(location: String, name: String) -> Result<AioDatabase, AioError>  where T: Default + Struct + Clone + Send + AioModel {       
+          let (db, _) = AioDatabase::create_with_policy::<T>(location, name, MigrationPolicy::default()).await?;
+          return Ok(db);
+     }
+
+     /// Create a locally persisted database, migrating an existing table according to `policy`.
+     /// 
+     /// With `MigrationPolicy::DryRun` the file is opened read only, or not at all if it doesn't exist.
+     pub async fn create_with_policy<T>(location: String, name: String, policy: MigrationPolicy) -> Result<(AioDatabase, MigrationReport), AioError>  where T: Default + Struct + Clone + Send + AioModel {       
+          return AioDatabase::create_with_migrator::<T>(location, name, Migrator::new(policy)).await;
+     }
+
+     /// Create a locally persisted database, migrating an existing table with `migrator`.
+     pub async fn create_with_migrator<T>(location: String, name: String, migrator: Migrator) -> Result<(AioDatabase, MigrationReport), AioError>  where T: Default + Struct + Clone + Send + AioModel {       
+          let db_location = Path::new(&location).join(format!("{}.db", name));
+
+          let options = if migrator.policy != MigrationPolicy::DryRun {
+               AioDatabaseOptions::new(db_location)
+          } else if db_location.exists() {
+               AioDatabaseOptions::new(db_location).read_only(true)
+          } else {
+               AioDatabaseOptions::in_memory()
+          };
+
+          let (db, report) = AioDatabase::init::<T>(options.connect().await?, name, migrator).await?;
+          db.conn.open_readers().await?;
+
+          return Ok((db, report));
+     }
+
+     /// Create an in-memory database.
+     pub async fn create_in_memory<T: Default + Struct + Clone + Send + AioModel>(name: String) -> Result<AioDatabase, AioError> {
+          let (db, _) = AioDatabase::create_in_memory_with_policy::<T>(name, MigrationPolicy::default()).await?;
+          return Ok(db);
+     }
+
+     /// Create an in-memory database. With `MigrationPolicy::DryRun` the table isn't created.
+     pub async fn create_in_memory_with_policy<T: Default + Struct + Clone + Send + AioModel>(name: String, policy: MigrationPolicy) -> Result<(AioDatabase, MigrationReport), AioError> {
+          return AioDatabase::create_in_memory_with_migrator::<T>(name, Migrator::new(policy)).await;
+     }
+
+     /// Create an in-memory database, the table is created according to `migrator`.
+     pub async fn create_in_memory_with_migrator<T: Default + Struct + Clone + Send + AioModel>(name: String, migrator: Migrator) -> Result<(AioDatabase, MigrationReport), AioError> {
+          return AioDatabase::init::<T>(AioDatabaseOptions::in_memory().connect().await?, name, migrator).await;
+     }
+
+     /// Opens (or creates) a database file that holds the tables of several models, e.g. `G:\\App.db`. Models are added with `register`.
+     /// Use `AioDatabaseOptions` to open it with other settings.
+     /// ```rust
+     /// let db = AioDatabase::open("G:\\App.db").await?;
+     /// db.register::<Person>().await?;
+     /// db.register::<Order>().await?;
+     ///
+     /// db.transaction(|tx| async move {
+     ///     tx.insert_value(&person).await?;
+     ///     tx.insert_value(&order).await?;
+     ///     Ok(())
+     /// }).await?;
+     /// ```
+     pub async fn open(path: impl AsRef<Path>) -> Result<AioDatabase, AioError> {
+
+          return AioDatabaseOptions::new(path).open().await;
+     }
+
+     /// Opens an in-memory database that holds the tables of several models. See `open`.
+     pub async fn open_in_memory() -> Result<AioDatabase, AioError> {
+
+          return AioDatabaseOptions::in_memory().open().await;
+     }
+
+     /// Creates or migrates the table of **T** with `MigrationPolicy::AdditiveOnly` and registers it, calls made with **T** use it from then on.
+     /// The table is named after `#[aio(table = "...")]` if set, otherwise after the struct.
+     pub async fn register<T: Default + Struct + Clone + Send + AioModel>(&self) -> Result<MigrationReport, AioError> {
+
+          return self.register_with_migrator::<T>(Migrator::default()).await;
+     }
+
+     /// Creates or migrates the table of **T** with `migrator` and registers it. See `register`.
+     pub async fn register_with_migrator<T: Default + Struct + Clone + Send + AioModel>(&self, migrator: Migrator) -> Result<MigrationReport, AioError> {
+          let type_name = std::any::type_name::<T>();
+          let struct_name = type_name.split('<').next().unwrap_or(type_name).rsplit("::").next().unwrap_or(type_name);
+
+          let (_, report) = self.register_table::<T>(struct_name.to_string(), migrator).await?;
+          return Ok(report);
+     }
+
+     /// Returns the typed handle to the table of the registered model **T**, sharing the connection.
+     pub fn table<T: Default + Struct + Clone + Send + AioModel>(&self) -> Result<AioTable<T>, AioError> {
+          let table = self.model::<T>()?;
+
+          return Ok(AioTable::new(AioDatabase {
+               conn: self.conn.clone(),
+               table: Some(table),
+               retry_policy: self.retry_policy
+          }));
+     }
+
+     pub(crate) fn new(aio_conn: AioDatabaseConnection) -> AioDatabase {
+          let retry_policy = RetryPolicy::for_busy_timeout(aio_conn.reader_settings.read().unwrap().busy_timeout);
+
+          return AioDatabase {
+               conn: Arc::new(aio_conn),
+               table: None,
+               retry_policy: retry_policy
+          };
+     }
+
+     /// Creates or migrates the table of the model. The table is named after `#[aio(table = "...")]` if set, otherwise `name`.
+     async fn init<T: Default + Struct + Clone + Send + AioModel>(aio_conn: AioDatabaseConnection, name: String, migrator: Migrator) -> Result<(AioDatabase, MigrationReport), AioError> {
+
+          return AioDatabase::new(aio_conn).register_table::<T>(name, migrator).await;
+     }
+
+     /// Migrates the table of the model, registers it and returns a handle to it.
+     async fn register_table<T: Default + Struct + Clone + Send + AioModel>(&self, name: String, migrator: Migrator) -> Result<(AioDatabase, MigrationReport), AioError> {
+          let model_info = T::model_info();
+          let name = model_info.table_name.clone().unwrap_or(name);
+
+          let generic_schema = get_schema_from_generic::<T>();
+          let model_version = model_info.version;
+          let statements = ModelStatements::new(&name, &generic_schema, Arc::new(model_info))?;
+
+          check_backfills::<T>(&generic_schema, &migrator)?;
+
+          let guard = self.conn.lock().await?;
+          let connection = &self.conn.sqlite_connection;
+
+          let upgraded_versions = upgrade_table(&name, model_version, &migrator, connection).await?;
+          let mut report = migrate(&name, &generic_schema, &migrator, connection).await?;
+          report.upgraded_versions = upgraded_versions;
+
+          // Unversioned models don't need a record, a table without one is at version 0
+          if model_version > 0 && migrator.policy != MigrationPolicy::DryRun {
+               write_model_version(&name, model_version, connection).await?;
+          }
+
+          let table = Arc::new(ModelTable {
+               name: name,
+               type_id: TypeId::of::<T>(),
+               schema: generic_schema,
+               statements: statements
+          });
+
+          // Backfills and steps run on the handle before the table is published, so no other call sees the model half migrated
+          let db = AioDatabase {
+               conn: self.conn.clone(),
+               table: Some(table.clone()),
+               retry_policy: self.retry_policy
+          };
+
+          let guard = if migrator.policy == MigrationPolicy::DryRun {
+               let history = read_migration_history(Some(&table.name), connection).await?;
+               report.pending_versions = pending_steps(&migrator.steps, &history)?.iter().map(|x| x.version()).collect();
+               guard
+          } else {
+               report.backfilled = backfill_columns::<T>(&db, &migrator, &report.added).await?;
+               let (applied_versions, guard) = run_migration_steps(&db, &migrator, guard).await?;
+               report.applied_versions = applied_versions;
+               guard
+          };
+
+          self.conn.tables.write().unwrap().insert(TypeId::of::<T>(), table);
+          drop(guard);
+
+          return Ok((db, report));
+     }
+
+     /// The table of the registered model **T**. A model the database wasn't created with or registered is an error, its fields don't match any table.
+     pub(crate) fn model<T: 'static>(&self) -> Result<Arc<ModelTable>, AioError> {
+          if let Some(table) = self.table.as_ref().filter(|x| x.type_id == TypeId::of::<T>()) {
+               return Ok(table.clone());
+          }
+
+          return self.conn.tables.read().unwrap().get(&TypeId::of::<T>()).cloned()
+               .ok_or_else(|| AioError::SchemaMismatch(format!("Model {} isn't registered.", std::any::type_name::<T>())));
+     }
+
+     /// The table of this handle, an error for a database returned by `open`.
+     pub(crate) fn own_table(&self) -> Result<&Arc<ModelTable>, AioError> {
+          return self.table.as_ref()
+               .ok_or_else(|| AioError::SchemaMismatch("The database was opened without a model, use table::<T>() or a call taking a model.".into()));
+     }
+
+     /// Set `journal_mode` to WAL. A file database opens its readers in WAL mode. See `set_journal_mode`.
+     pub async fn set_wal_mode(&self, wal_mode: WalMode) -> Result<(), AioError> {
+          self.set_journal_mode(JournalMode::from(wal_mode)).await?;
+          return Ok(());
+     }
+
+     /// Set `journal_mode` to `delete` in order to be compatible to older SQLite versions.
+     /// The readers are closed, reads run on the writer. See `set_journal_mode`.
+     pub  async fn set_wal_mode_to_rollback(&self) -> Result<(), AioError> {
+          self.set_journal_mode(JournalMode::Delete).await?;
+          return Ok(());
+     }
+
+     /// Sets `journal_mode` and returns the mode SQLite switched to. It can differ from `journal_mode`, e.g. an in-memory database
+     /// stays in **Memory** (or **Off**) mode. Waits for open transactions, the readers are reopened in WAL mode and closed otherwise.
+     pub async fn set_journal_mode(&self, journal_mode: JournalMode) -> Result<JournalMode, AioError> {
+          let _guard = self.conn.lock().await?;
+
+          self.conn.close_readers().await?;
+          let result = set_journal_mode(&self.conn.sqlite_connection, journal_mode).await;
+          self.conn.open_readers().await?;
+
+          return result;
+     }
+
+     /// Get the current `journal_mode` of the database.
+     pub async fn get_journal_mode(&self) -> Result<JournalMode, AioError> {
+          let _guard = self.conn.lock().await?;
+
+          return get_journal_mode(&self.conn.sqlite_connection).await;
+     }
+
+     /// Reads and sets the pragmas of the database: cache_size, mmap_size, temp_store, foreign_keys, busy_timeout,
+     /// wal_autocheckpoint, journal_size_limit and locking_mode.
+     pub fn pragmas(&self) -> Pragmas<'_> {
+          return Pragmas::new(self);
+     }
+
+     /// The current value of every setting of the database, see `DatabaseSettings`.
+     pub async fn settings_snapshot(&self) -> Result<DatabaseSettings, AioError> {
+          return self.pragmas().snapshot().await;
+     }
+
+     /// Sets how many read-only connections a file database in WAL mode opens, default is 4. Reads (`get_*`, `any`, `count`, `all`) outside
+     /// of a transaction run on an idle reader, all other calls and transactions on the single writer. 0 runs the reads on the writer.
+     pub async fn set_reader_pool_size(&self, size: usize) -> Result<(), AioError> {
+          let _guard = self.conn.lock().await?;
+
+          self.conn.reader_pool_size.store(size, Ordering::Relaxed);
+
+          return self.conn.open_readers().await;
+     }
+
+     /// Get the size of the reader pool and how long reads and writes waited for a connection.
+     pub fn pool_stats(&self) -> PoolStats {
+          let (readers, idle_readers) = match self.conn.reader_pool() {
+               Some(pool) => (pool.readers.len(), pool.idle()),
+               None => (0, 0)
+          };
+
+          let (reader_acquisitions, reader_wait, max_reader_wait) = self.conn.reader_waits.get();
+          let (writer_acquisitions, writer_wait, max_writer_wait) = self.conn.writer_waits.get();
+
+          return PoolStats {
+               readers: readers,
+               idle_readers: idle_readers,
+               reader_acquisitions: reader_acquisitions,
+               reader_wait: reader_wait,
+               max_reader_wait: max_reader_wait,
+               writer_acquisitions: writer_acquisitions,
+               writer_wait: writer_wait,
+               max_writer_wait: max_writer_wait
+          };
+     }
+
+     /// Sets how many attempts an insert, update or delete makes while the database is busy or locked. See `set_retry_policy`.
+     pub fn set_query_retries(&mut self, retries: u32) {
+          self.retry_policy.max_attempts = retries;
+     }
+
+     /// Sets how inserts, updates and deletes of this handle are retried while the database is busy or locked. Other errors are returned immediately.
+     /// Default is `RetryPolicy::none()`, SQLite waits for the lock for the busy_timeout, or `RetryPolicy::default()` if the busy_timeout is 0. See `RetryPolicy`.
+     pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+          self.retry_policy = retry_policy;
+     }
+
+     /// Get the retry policy of this handle.
+     pub fn get_retry_policy(&self) -> RetryPolicy {
+          return self.retry_policy;
+     }
+
+     /// Sets how many prepared statements are kept per connection, the writer and each reader. Default is 16, 0 disables the cache.
+     pub async fn set_statement_cache_capacity(&self, capacity: usize) -> Result<(), AioError> {
+          let _guard = self.conn.lock().await?;
+          let mut connections = vec![(self.conn.sqlite_connection.clone(), self.conn.statement_cache.clone())];
+
+          if let Some(pool) = self.conn.reader_pool() {
+               connections.extend(pool.readers.iter().map(|x| (x.sqlite_connection.clone(), x.statement_cache.clone())));
+          }
+
+          for (sqlite_connection, statement_cache) in connections {
+               sqlite_connection.call(move |conn| {
+                    statement_cache.set_capacity(conn, capacity);
+                    Ok(())
+               }).await?;
+          }
+
+          return Ok(());
+     }
+
+     /// Get the hit / miss counters of the prepared statement caches of the writer and the readers, and their capacity.
+     /// The counters are approximate: they are an estimate of rusqlite's cache, which doesn't report whether a statement was reused.
+     pub fn statement_cache_stats(&self) -> StatementCacheStats {
+          let mut stats = self.conn.statement_cache.stats();
+
+          if let Some(pool) = self.conn.reader_pool() {
+               for reader in pool.readers.iter() {
+                    let reader_stats = reader.statement_cache.stats();
+                    stats.hits += reader_stats.hits;
+                    stats.misses += reader_stats.misses;
+               }
+          }
+
+          return stats;
+     }
+
+     /// Get the name of the database and table's name as well. Empty for a database returned by `open`.
+     pub fn get_name(&self) -> &str {
+          return self.table.as_ref().map(|x| x.name.as_str()).unwrap_or_default();
+     }
+
+     /// Get the schema of the struct / database. Empty for a database returned by `open`.
+     pub fn get_schema(&self) -> &Vec<Schema> {
+          static NO_SCHEMA: Vec<Schema> = Vec::new();
+          return self.table.as_ref().map(|x| &x.schema).unwrap_or(&NO_SCHEMA);
+     }
+
+     /// Reverts the versioned steps of `migrator` applied to the table of the handle after `version`, newest first, with their down step. Returns the reverted versions.
+     /// An error for a database returned by `open`, use the database of `table::<T>()`. Fails without reverting anything if the recorded history diverges from the steps, and stops at the first step without a down step.
+     pub async fn migrate_down(&self, migrator: &Migrator, version: u32) -> Result<Vec<u32>, AioError> {
+          let guard = self.conn.lock().await?;
+
+          return revert_migration_steps(self, migrator, version, guard).await;
+     }
+
+     /// Get the versioned steps recorded in the `_aio_migrations` table for the table of the handle, ordered by version.
+     /// A database returned by `open` gets the steps of every table, ordered by table and version.
+     pub async fn migration_history(&self) -> Result<Vec<AppliedMigration>, AioError> {
+          let _guard = self.conn.lock().await?;
+          let name = self.table.as_ref().map(|x| x.name.as_str());
+
+          return read_migration_history(name, &self.conn.sqlite_connection).await;
+     }
+
+     /// Reads the live schema of the table from SQLite: its columns with their declared type, nullability, default and primary key, its indexes and its foreign keys.
+     pub async fn describe(&self) -> Result<TableDescription, AioError> {
+          let _guard = self.conn.lock().await?;
+
+          let name = &self.own_table()?.name;
+
+          return describe_table(name, &self.conn.sqlite_connection).await?
+               .ok_or_else(|| AioError::SchemaMismatch(format!("Table {} doesn't exist.", name)));
+     }
+
+     /// If set_synchronous(true) then the PRAGMA synchronous will equal to NORMAL (recommended) or false for PRAGMA synchronous to equal to OFF. 
+     /// That way transaction will be allowed to be asynchronous which may increase performance but in case of an accident the DB may be corrupted.
+     pub async fn set_synchronous(&self, val: bool) -> Result<(), AioError> {
+          let _guard = self.conn.lock().await?;
+
+          return change_synchronous_settings(&self.conn.sqlite_connection, val).await;
+     }
+
+     /// Inserts a **T** value in the database and returns its rowid, which is also the id of an `#[aio(auto_increment)]` key. Returns the error of the last attempt if the insertion was still busy after the attempts of the retry policy.
+     pub async fn insert_value<T: Default + Struct + Clone + Send>(&self, value: &T) -> Result<i64, AioError> {
+          
+          return self.insert_value_in(value, None).await;
+     }
+
+     /// Inserts all values in a single transaction, reusing one prepared statement. If a row fails nothing is inserted and its error is returned.
+     pub async fn insert_many<T: Default + Struct + Clone + Send>(&self, values: &[T]) -> Result<InsertManyResult, AioError> {
+
+          return self.insert_many_in::<T, _>(values.iter(), None, BulkInsertMode::AllOrNothing).await;
+     }
+
+     /// Like `insert_many`, with `BulkInsertMode::SkipFailed` the failed rows are skipped and reported with their index instead.
+     pub async fn insert_many_with<T: Default + Struct + Clone + Send>(&self, values: &[T], mode: BulkInsertMode) -> Result<InsertManyResult, AioError> {
+
+          return self.insert_many_in::<T, _>(values.iter(), None, mode).await;
+     }
+
+     /// Inserts all values of the iterator in a single transaction. If a row fails nothing is inserted and its error is returned.
+     pub async fn insert_iter<T: Default + Struct + Clone + Send>(&self, values: impl IntoIterator<Item = T>) -> Result<InsertManyResult, AioError> {
+
+          return self.insert_many_in::<T, _>(values, None, BulkInsertMode::AllOrNothing).await;
+     }
+
+     /// Like `insert_iter`, with `BulkInsertMode::SkipFailed` the failed rows are skipped and reported with their index instead.
+     pub async fn insert_iter_with<T: Default + Struct + Clone + Send>(&self, values: impl IntoIterator<Item = T>, mode: BulkInsertMode) -> Result<InsertManyResult, AioError> {
+
+          return self.insert_many_in::<T, _>(values, None, mode).await;
+     }
+
+     /// Inserts a **T** value, or updates every other field of the row with the same `conflict_fields` (e.g. a primary key or the columns of
+     /// a `create_unique_index`) in a single `INSERT ... ON CONFLICT DO UPDATE` statement. Returns whether the row was inserted or updated.
+     pub async fn upsert<T: Default + Struct + Clone + Send>(&self, value: &T, conflict_fields: Vec<String>) -> Result<UpsertResult, AioError> {
+
+          return self.upsert_in(value, conflict_fields, UpsertAction::UpdateAll, None).await;
+     }
+
+     /// Like `upsert`, resolving the conflict with `action`: update every field, only some of them, or nothing.
+     pub async fn upsert_with<T: Default + Struct + Clone + Send>(&self, value: &T, conflict_fields: Vec<String>, action: UpsertAction) -> Result<UpsertResult, AioError> {
+
+          return self.upsert_in(value, conflict_fields, action, None).await;
+     }
+
+     /// Returns the row whose primary key (or rowid if the model has no key) equals `id`, or **AioError::NotFound**.
+     pub async fn get_by_id<T: Default + Struct + Clone + Send>(&self, id: impl ToSql) -> Result<T, AioError> {
+          return self.get_by_id_in(to_sql_value(&id)?, None).await;
+     }
+
+     /// Updates every field except the primary key of the row whose key (or rowid) equals `id`. Returns the number of rows affected.
+     pub async fn update_by_id<T: Default + Struct + Clone + Send>(&self, id: impl ToSql, value: &T) -> Result<usize, AioError> {
+          return self.update_by_id_in(to_sql_value(&id)?, value, None).await;
+     }
+
+     /// Deletes the row whose primary key (or rowid) equals `id`. Returns the number of rows affected.
+     pub async fn delete_by_id<T: Default + Struct + Clone + Send>(&self, id: impl ToSql) -> Result<usize, AioError> {
+          return self.delete_by_id_in::<T>(to_sql_value(&id)?, None).await;
+     }
+
+     /// Creates a QueryBuilder that allows to chain query filters for different field / columns.
+     pub fn query<'a>(&'a self) -> QueryBuilder<'a> {
+          return QueryBuilder {
+               table_name: self.get_name().to_string(),
+               query_options: Vec::default(),
+               db: self,
+               transaction: None
+          }
+     }
+
+     /// Creates an OwnedQueryBuilder, holding a clone of the database so it can be moved into another task.
+     pub fn query_owned(&self) -> OwnedQueryBuilder {
+
+          return OwnedQueryBuilder::new(self.clone());
+     }
+
+     /// Begins a `DEFERRED` transaction. Until it's committed or rolled back, other calls made through this `AioDatabase` or its clones wait for it,
+     /// except reads running on the readers of the pool, which see the rows committed before it.
+     pub async fn begin<'a>(&'a self) -> Result<AioTransaction<'a>, AioError> {
+
+          return AioTransaction::begin(self, TransactionMode::Deferred).await;
+     }
+
+     /// Begins a transaction with the given `TransactionMode`.
+     pub async fn begin_with<'a>(&'a self, mode: TransactionMode) -> Result<AioTransaction<'a>, AioError> {
+
+          return AioTransaction::begin(self, mode).await;
+     }
+
+     /// Runs the closure in a `DEFERRED` transaction. Commits if it returns `Ok`, rolls back if it returns `Err` (or the future is dropped).
+     /// Inside the closure use the given `AioTransaction`, not the `AioDatabase`, which waits for the transaction to finish.
+     /// ### Example
+     /// ```rust
+     /// file_db.transaction(|tx| async move {
+     ///     tx.insert_value(&person).await?;
+     ///     tx.query()
+     ///         .field("name")
+     ///         .where_is(Operator::Eq("Mylo".into()), None)
+     ///         .partial_update::<Person>("age".into(), "5".into()).await?;
+     ///     Ok(())
+     /// }).await?;
+     /// ```
+     pub async fn transaction<'a, F, Fut, R>(&'a self, function: F) -> Result<R, AioError> 
+     where F: FnOnce(AioTransaction<'a>) -> Fut, Fut: Future<Output = Result<R, AioError>> {
+
+          return self.transaction_with(TransactionMode::Deferred, function).await;
+     }
+
+     /// Runs the closure in a transaction with the given `TransactionMode`. See `transaction`.
+     pub async fn transaction_with<'a, F, Fut, R>(&'a self, mode: TransactionMode, function: F) -> Result<R, AioError> 
+     where F: FnOnce(AioTransaction<'a>) -> Fut, Fut: Future<Output = Result<R, AioError>> {
+          let transaction = AioTransaction::begin(self, mode).await?;
+
+          match function(transaction.clone()).await {
+               Ok(value) => {
+                    transaction.commit().await?;
+                    return Ok(value);
+               },
+               Err(error) => {
+                    if transaction.is_active() {
+                         transaction.rollback().await?;
+                    }
+                    return Err(error);
+               }
+          }
+     }
+
+     /// Takes an idle reader for a read outside of a transaction, otherwise reserves the writer like `acquire`.
+     async fn acquire_read<'a>(&'a self, transaction: Option<&AioTransaction<'_>>) -> Result<ReadConnection<'a>, AioError> {
+          if transaction.is_none()
+               && let Some(pool) = self.conn.reader_pool()
+               && let Some(reader) = pool.acquire(&self.conn.reader_waits).await {
+               return Ok(ReadConnection::Reader(reader));
+          }
+
+          let guard = self.acquire(transaction).await?;
+          return Ok(ReadConnection::Writer { connection: &self.conn, _guard: guard });
+     }
+
+     /// Reserves the connection for a single call, or checks that the transaction it runs in is still open.
+     async fn acquire(&self, transaction: Option<&AioTransaction<'_>>) -> Result<Option<OwnedMutexGuard<ConnectionState>>, AioError> {
+          return match transaction {
+               Some(transaction) => {
+                    transaction.ensure_active().await?;
+                    Ok(None)
+               },
+               None => Ok(Some(self.conn.lock().await?))
+          };
+     }
+
+     pub(crate) async fn insert_value_in<T: Default + Struct + Clone + Send>(&self, value: &T, transaction: Option<&AioTransaction<'_>>) -> Result<i64, AioError> {
+          let table = self.model::<T>()?;
+          let _guard = self.acquire(transaction).await?;
+
+          return insert_value::<T>(value, &table.statements, &self.conn, &self.retry_policy).await;
+     }
+     
+     pub(crate) async fn upsert_in<T: Default + Struct + Clone + Send>(&self, value: &T, conflict_fields: Vec<String>, action: UpsertAction, transaction: Option<&AioTransaction<'_>>) -> Result<UpsertResult, AioError> {
+          let table = self.model::<T>()?;
+          let _guard = self.acquire(transaction).await?;
+
+          return upsert_value::<T>(value, &table.statements, conflict_fields, action, &self.conn, &self.retry_policy).await;
+     }
+
+     pub(crate) async fn insert_many_in<T: Default + Struct + Clone + Send, B: Borrow<T>>(&self, values: impl IntoIterator<Item = B>, transaction: Option<&AioTransaction<'_>>, mode: BulkInsertMode) -> Result<InsertManyResult, AioError> {
+          let table = self.model::<T>()?;
+          let mut guard = self.acquire(transaction).await?;
+          let mut savepoint = OpenSavepoint::new("aio_insert_many", guard.as_deref_mut(), transaction);
+
+          return insert_many::<T, B>(values, &table.statements, &self.conn, mode, &mut savepoint, &self.retry_policy).await;
+     }
+
+     pub(crate) async fn get_by_id_in<T: Default + Struct + Clone + Send>(&self, id: Value, transaction: Option<&AioTransaction<'_>>) -> Result<T, AioError> {
+          let query = BoundQuery { query: self.model::<T>()?.statements.get_by_id_query.clone(), params: vec![id] };
+
+          return self.get_single_value::<T>(query, transaction).await;
+     }
+
+     pub(crate) async fn update_by_id_in<T: Default + Struct + Clone + Send>(&self, id: Value, value: &T, transaction: Option<&AioTransaction<'_>>) -> Result<usize, AioError> {
+          let table = self.model::<T>()?;
+          let _guard = self.acquire(transaction).await?;
+
+          return update_by_id::<T>(id, value, &table.statements, &self.conn, &self.retry_policy).await;
+     }
+
+     pub(crate) async fn delete_by_id_in<T: Default + Struct + Clone + Send>(&self, id: Value, transaction: Option<&AioTransaction<'_>>) -> Result<usize, AioError> {
+          let table = self.model::<T>()?;
+          let _guard = self.acquire(transaction).await?;
+
+          return delete_by_id(id, &table.statements, &self.conn, &self.retry_policy).await;
+     }
+
+     pub(crate) async fn get_single_value<T: Default + Struct + Clone + Send>(&self, query_string: BoundQuery, transaction: Option<&AioTransaction<'_>>) -> Result<T, AioError> {
+          let table = self.model::<T>()?;
+          let connection = self.acquire_read(transaction).await?;
+
+          let query_result = QueryRowResult::<T>::new(query_string, table.statements.model_info.clone(), &connection).await?;
+          return query_result.value.ok_or(AioError::NotFound);
+     }
+
+     pub(crate) async fn get_many_values<T: Default + Struct + Clone + Send>(&self, query_string: BoundQuery, transaction: Option<&AioTransaction<'_>>) -> Result<Vec<T>, AioError> {
+          let table = self.model::<T>()?;
+          let connection = self.acquire_read(transaction).await?;
+
+          let query_result = QueryRowsResult::<T>::new_many(query_string, table.statements.model_info.clone(), &connection).await?;
+          return Ok(query_result.value);
+     }
+
+     pub(crate) async fn update_value<T: Default + Struct + Clone + Send>(&self, value: T, where_query: BoundQuery, transaction: Option<&AioTransaction<'_>>) -> Result<usize, AioError> {
+          let table = self.model::<T>()?;
+          let _guard = self.acquire(transaction).await?;
+
+          return update_value::<T>(&value, &table.statements, where_query, &self.conn, &self.retry_policy).await;
+     }
+
+     pub(crate) async fn partial_update<T: Default + Struct + Clone + Send>(&self, field_name: String, field_value: Option<String>, where_query: BoundQuery, transaction: Option<&AioTransaction<'_>>) ->  Result<usize, AioError> {
+          let table = self.model::<T>()?;
+          let field = find_schema_field(&table.schema, table.statements.column_name(&field_name).unwrap_or(&field_name))?;
+          let _guard = self.acquire(transaction).await?;
+
+          return partial_update(field, field_value, &table.name, where_query, &self.conn, &self.retry_policy).await;
+     }
+
+     pub(crate) async fn delete_value<T: Default + Struct + Clone + Send>(&self, where_query: BoundQuery, transaction: Option<&AioTransaction<'_>>) -> Result<usize, AioError> {
+          let table = self.model::<T>()?;
+          let _guard = self.acquire(transaction).await?;
+
+          return delete_value(&table.name, where_query, &self.conn, &self.retry_policy).await;
+     }
+
+     pub(crate) async fn any<T: Default + Struct + Clone + Send>(&self, where_query: BoundQuery, transaction: Option<&AioTransaction<'_>>) -> Result<bool, AioError> {
+          let table = self.model::<T>()?;
+          let connection = self.acquire_read(transaction).await?;
+
+          let count = count_rows(any_count_query(&table.name, where_query).await, &connection).await?;
+          return Ok(count > 0);
+     }
+
+     pub(crate) async fn count<T: Default + Struct + Clone + Send>(&self, where_query: BoundQuery, transaction: Option<&AioTransaction<'_>>) -> Result<u64, AioError> {
+          let table = self.model::<T>()?;
+          let connection = self.acquire_read(transaction).await?;
+
+          return count_rows(any_count_query(&table.name, where_query).await, &connection).await;
+     }
+
+     pub(crate) async fn all<T: Default + Struct + Clone + Send>(&self, where_query: BoundQuery, transaction: Option<&AioTransaction<'_>>) -> Result<bool, AioError> {
+          let table = self.model::<T>()?;
+          let connection = self.acquire_read(transaction).await?;
+
+          let all_records = count_rows(all_query(&table.name).await, &connection).await?;
+          let any_records = count_rows(any_count_query(&table.name, where_query).await, &connection).await?;
+          return Ok(any_records == all_records);
+     }
+
+     /// Create a non-unique index for a set of columns / struct fields if doesn't exist. Might lead to better performance.
+     pub async fn create_index<T: Default + Struct + Clone + Send> (
+          &self,
+          index_name: &str,
+          columns: Vec<String>) -> Result<(), AioError> {
+
+          return self.create_index_of::<T>(index_name, columns, false).await;
+     }
+
+     /// Create a unique index for a set of columns / struct fields if doesn't exist. Might lead to better performance.
+     pub async fn create_unique_index<T: Default + Struct + Clone + Send> (
+          &self,
+          index_name: &str,
+          columns: Vec<String>) -> Result<(), AioError> {
+
+          return self.create_index_of::<T>(index_name, columns, true).await;
+     }
+
+     async fn create_index_of<T: Default + Struct + Clone + Send>(&self, index_name: &str, columns: Vec<String>, unique: bool) -> Result<(), AioError> {
+          let query = create_index::<T>(index_name, &self.model::<T>()?.statements, columns, unique)?;
+          let _guard = self.conn.lock().await?;
+
+          self.conn.sqlite_connection.call(move |conn| {
+               conn.execute(&query, ())?;
+               Ok(())
+          }).await?;
+
+          return Ok(());
+     }
+
+     /// Drop an index if exists.
+     pub async fn drop_index(
+          &self,
+          index_name: &str) -> Result<(), AioError> {
+          let query = drop_index(index_name);
+          let _guard = self.conn.lock().await?;
+          
+          self.conn.sqlite_connection.call(move |conn| { 
+               conn.execute(&query, ())?;
+               Ok(())
+          }).await?;
+
+          return Ok(());
+     }
+
+     /// Serializes a struct with bincode, to be stored in a `Vec<u8>` field. Returns **AioError::Serialization** if it can't be serialized.
+     pub fn get_bytes<S: Serialize>(struct_to_bytes: S) -> Result<Vec<u8>, AioError> {
+          return bincode::serialize(&struct_to_bytes)
+               .map_err(|error| AioError::Serialization(error.to_string()));
+     }
+
+     /// Deserializes a struct stored with `get_bytes`. Returns **AioError::Serialization** if the bytes don't hold an **S**.
+     pub fn get_struct<'a, S: Deserialize<'a>>(vec_u8_to_struct: &'a [u8]) -> Result<S, AioError> {
+          return bincode::deserialize(vec_u8_to_struct)
+               .map_err(|error| AioError::Serialization(error.to_string()));
+     }
+}
+async fn count_rows(query: BoundQuery, connection: &ReadConnection<'_>) -> Result<u64, AioError> {
+     let query_result = QueryRowResult::<AnyCountResult>::new(query, Arc::default(), connection).await?;
+     return Ok(query_result.value.map(|x| x.count_total).unwrap_or(0));
+}
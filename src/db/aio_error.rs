@@ -0,0 +1,218 @@
+use std::fmt;
+
+use rusqlite::{ffi, ErrorCode};
+
+use super::models::MigrationReport;
+
+/// Error returned by every `AioDatabase` and `QueryBuilder` call.
+///
+/// Variants that originate from SQLite carry the primary `code` and the `extended_code`, so callers can
+/// react differently to e.g. a unique violation (`SQLITE_CONSTRAINT_UNIQUE`) and a locked database.
+#[derive(Debug)]
+pub enum AioError {
+     /// The database file is being written by another connection (`SQLITE_BUSY`).
+     Busy {
+          code: ErrorCode,
+          extended_code: i32,
+          message: String
+     },
+     /// A table is locked by another statement of the same connection (`SQLITE_LOCKED`).
+     Locked {
+          code: ErrorCode,
+          extended_code: i32,
+          message: String
+     },
+     /// A `UNIQUE`, `PRIMARY KEY`, `NOT NULL`, `CHECK` or `FOREIGN KEY` constraint failed.
+     /// `constraint` is the detail reported by SQLite (e.g. `Person.first_name`) and `index_name` the index that rejected the row, when it could be resolved.
+     ConstraintViolation {
+          constraint: String,
+          index_name: Option<String>,
+          code: ErrorCode,
+          extended_code: i32,
+          message: String
+     },
+     /// The struct of type **T** and the table / row returned by the database do not match.
+     SchemaMismatch(String),
+     /// A struct field has a type that can't be mapped to a SQLite column.
+     UnsupportedFieldType(String),
+     /// The database file couldn't be created, opened, read or written.
+     Io {
+          code: Option<ErrorCode>,
+          extended_code: Option<i32>,
+          message: String
+     },
+     /// The generated or provided SQL is not valid.
+     SqlSyntax {
+          code: ErrorCode,
+          extended_code: i32,
+          message: String
+     },
+     /// A struct couldn't be serialized to bytes by `get_bytes`, or deserialized by `get_struct`.
+     Serialization(String),
+     /// The query matched no row.
+     NotFound,
+     /// The background connection thread has been closed.
+     ConnectionClosed,
+     /// The transaction was already committed or rolled back.
+     TransactionFinished,
+     /// The table doesn't match the model and `MigrationPolicy::Fail` doesn't allow migrating it.
+     MigrationRequired(Box<MigrationReport>),
+     /// The versions recorded in `_aio_migrations` don't match the steps of the `Migrator`: a step was removed, changed or added before the last applied one.
+     MigrationHistoryDiverged {
+          version: u32,
+          message: String
+     },
+     /// Any other SQLite failure.
+     Sqlite {
+          code: ErrorCode,
+          extended_code: i32,
+          message: String
+     },
+     /// Any other non SQLite failure.
+     Other(String)
+}
+
+impl AioError {
+     /// The primary SQLite error code, if the error came from SQLite.
+     pub fn sqlite_code(&self) -> Option<ErrorCode> {
+          return match self {
+               AioError::Busy { code, .. } => Some(*code),
+               AioError::Locked { code, .. } => Some(*code),
+               AioError::ConstraintViolation { code, .. } => Some(*code),
+               AioError::SqlSyntax { code, .. } => Some(*code),
+               AioError::Sqlite { code, .. } => Some(*code),
+               AioError::Io { code, .. } => *code,
+               _ => None
+          };
+     }
+
+     /// The extended SQLite error code (e.g. `SQLITE_CONSTRAINT_UNIQUE`), if the error came from SQLite.
+     pub fn extended_code(&self) -> Option<i32> {
+          return match self {
+               AioError::Busy { extended_code, .. } => Some(*extended_code),
+               AioError::Locked { extended_code, .. } => Some(*extended_code),
+               AioError::ConstraintViolation { extended_code, .. } => Some(*extended_code),
+               AioError::SqlSyntax { extended_code, .. } => Some(*extended_code),
+               AioError::Sqlite { extended_code, .. } => Some(*extended_code),
+               AioError::Io { extended_code, .. } => *extended_code,
+               _ => None
+          };
+     }
+
+     /// Returns true if the database was busy or locked. These errors are transient and the call can be retried.
+     pub fn is_busy(&self) -> bool {
+          return matches!(self, AioError::Busy { .. } | AioError::Locked { .. });
+     }
+
+     /// Returns true if a `UNIQUE` or `PRIMARY KEY` constraint rejected the row.
+     pub fn is_unique_violation(&self) -> bool {
+          return matches!(
+               self.extended_code(),
+               Some(ffi::SQLITE_CONSTRAINT_UNIQUE) | Some(ffi::SQLITE_CONSTRAINT_PRIMARYKEY)
+          );
+     }
+
+     /// Returns true if the query matched no row.
+     pub fn is_not_found(&self) -> bool {
+          return matches!(self, AioError::NotFound);
+     }
+
+     fn from_sqlite_failure(error: ffi::Error, message: Option<String>) -> AioError {
+          let code = error.code;
+          let extended_code = error.extended_code;
+          let message = message.unwrap_or_else(|| error.to_string());
+
+          return match code {
+               ErrorCode::DatabaseBusy => AioError::Busy { code, extended_code, message },
+               ErrorCode::DatabaseLocked => AioError::Locked { code, extended_code, message },
+               ErrorCode::ConstraintViolation => {
+                    let constraint = match message.split_once(": ") {
+                         Some((_, constraint)) => constraint.to_string(),
+                         None => message.clone()
+                    };
+
+                    AioError::ConstraintViolation { constraint, index_name: None, code, extended_code, message }
+               },
+               ErrorCode::SystemIoFailure |
+               ErrorCode::CannotOpen |
+               ErrorCode::DiskFull |
+               ErrorCode::ReadOnly |
+               ErrorCode::DatabaseCorrupt |
+               ErrorCode::NotADatabase |
+               ErrorCode::PermissionDenied => AioError::Io { code: Some(code), extended_code: Some(extended_code), message },
+               ErrorCode::SchemaChanged |
+               ErrorCode::TypeMismatch => AioError::SchemaMismatch(message),
+               ErrorCode::Unknown => {
+                    if message.contains("no such column") ||
+                       message.contains("no such table") ||
+                       message.contains("has no column named") {
+                         AioError::SchemaMismatch(message)
+                    } else {
+                         AioError::SqlSyntax { code, extended_code, message }
+                    }
+               },
+               _ => AioError::Sqlite { code, extended_code, message }
+          };
+     }
+}
+
+impl fmt::Display for AioError {
+     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+          match self {
+               AioError::Busy { message, .. } => write!(f, "Database is busy: {}", message),
+               AioError::Locked { message, .. } => write!(f, "Database is locked: {}", message),
+               AioError::ConstraintViolation { message, index_name: Some(index_name), .. } => write!(f, "Constraint violation on index {}: {}", index_name, message),
+               AioError::ConstraintViolation { message, .. } => write!(f, "Constraint violation: {}", message),
+               AioError::SchemaMismatch(message) => write!(f, "Schema mismatch: {}", message),
+               AioError::UnsupportedFieldType(field_type) => write!(f, "{} type not supported.", field_type),
+               AioError::Io { message, .. } => write!(f, "I/O error: {}", message),
+               AioError::SqlSyntax { message, .. } => write!(f, "SQL error: {}", message),
+               AioError::Serialization(message) => write!(f, "Serialization error: {}", message),
+               AioError::NotFound => write!(f, "No value / row matched the query."),
+               AioError::ConnectionClosed => write!(f, "The connection to the database is closed."),
+               AioError::TransactionFinished => write!(f, "The transaction was already committed or rolled back."),
+               AioError::MigrationRequired(report) => write!(f, "Table {} doesn't match the model and the migration policy doesn't allow migrating it.", report.table_name),
+               AioError::MigrationHistoryDiverged { version, message } => write!(f, "Migration history diverged at version {}: {}", version, message),
+               AioError::Sqlite { message, .. } => write!(f, "SQLite error: {}", message),
+               AioError::Other(message) => write!(f, "{}", message)
+          }
+     }
+}
+
+impl std::error::Error for AioError { }
+
+impl From<tokio_rusqlite::Error> for AioError {
+     fn from(error: tokio_rusqlite::Error) -> Self {
+          return match error {
+               tokio_rusqlite::Error::Rusqlite(error) => AioError::from(error),
+               tokio_rusqlite::Error::ConnectionClosed => AioError::ConnectionClosed,
+               tokio_rusqlite::Error::Close((_, error)) => AioError::from(error),
+               error => AioError::Other(error.to_string())
+          };
+     }
+}
+
+impl From<rusqlite::Error> for AioError {
+     fn from(error: rusqlite::Error) -> Self {
+          use rusqlite::Error;
+
+          return match error {
+               Error::SqliteFailure(error, message) => AioError::from_sqlite_failure(error, message),
+               Error::SqlInputError { error, msg, .. } => AioError::from_sqlite_failure(error, Some(msg)),
+               Error::QueryReturnedNoRows => AioError::NotFound,
+               Error::InvalidColumnIndex(_) |
+               Error::InvalidColumnName(_) |
+               Error::InvalidColumnType(_, _, _) |
+               Error::FromSqlConversionFailure(_, _, _) |
+               Error::IntegralValueOutOfRange(_, _) => AioError::SchemaMismatch(error.to_string()),
+               Error::InvalidPath(_) => AioError::Io { code: None, extended_code: None, message: error.to_string() },
+               error => AioError::Other(error.to_string())
+          };
+     }
+}
+
+impl From<std::io::Error> for AioError {
+     fn from(error: std::io::Error) -> Self {
+          return AioError::Io { code: None, extended_code: None, message: error.to_string() };
+     }
+}
@@ -0,0 +1,229 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use bevy_reflect::Struct;
+use rusqlite::ToSql;
+use rusqlite::types::Value;
+
+use super::aio_error::AioError;
+use super::aio_transaction::AioTransaction;
+use super::internal::helpers::to_sql_value;
+use super::internal::migrations::upgrade_rows;
+use super::models::AioModel;
+use super::models::MigrationPolicy;
+
+/// Converts a stored value of a column whose type changed into a value of the new type.
+pub type ColumnConverter = Arc<dyn Fn(Value) -> Result<Value, AioError> + Send + Sync>;
+
+/// Computes the value of a new column from an existing row, the row is a `&T` of the model.
+pub(crate) type ColumnBackfill = Arc<dyn Fn(&dyn Any) -> Result<Value, AioError> + Send + Sync>;
+
+/// Rewrites the rows of a table from one model to the next, given the connection (in a transaction) and the table name.
+pub(crate) type ModelUpgradeFn = Arc<dyn Fn(&rusqlite::Connection, &str) -> Result<usize, AioError> + Send + Sync>;
+
+/// An upgrade registered with `Migrator::upgrade`, converting tables below `version` to it.
+#[derive(Clone)]
+pub(crate) struct ModelUpgrade {
+     pub(crate) version: u32,
+     pub(crate) upgrade: ModelUpgradeFn
+}
+
+/// The future returned by the closure of a `MigrationStep`.
+pub type MigrationFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AioError>> + Send + 'a>>;
+
+/// The closure of a `MigrationStep`, run in the transaction of the step.
+pub type MigrationFn = Arc<dyn for<'a> Fn(AioTransaction<'a>) -> MigrationFuture<'a> + Send + Sync>;
+
+/// What a `MigrationStep` executes, embedded SQL or a Rust closure.
+#[derive(Clone)]
+pub enum MigrationAction {
+     Sql(String),
+     Function(MigrationFn)
+}
+
+/// A numbered migration step. Its up action runs once, when a database whose `_aio_migrations` table doesn't record the version for the table of the model is opened.
+/// Every model has its own versions, two models can both have a step 1.
+/// ```rust
+/// let step = MigrationStep::sql(1, "add_country", "ALTER TABLE Person ADD COLUMN country TEXT")
+///     .down_sql("ALTER TABLE Person DROP COLUMN country");
+///
+/// let backfill = MigrationStep::new(2, "backfill_country", |tx| Box::pin(async move {
+///     tx.execute_batch("UPDATE Person SET country = 'BG' WHERE country IS NULL").await
+/// }));
+/// ```
+#[derive(Clone)]
+pub struct MigrationStep {
+     pub(crate) version: u32,
+     pub(crate) name: String,
+     pub(crate) up: MigrationAction,
+     pub(crate) down: Option<MigrationAction>
+}
+
+impl MigrationStep {
+     /// A step running a Rust closure. Its checksum only covers the version and the name, changes to the closure can't be detected.
+     pub fn new<F>(version: u32, name: &str, up: F) -> MigrationStep
+     where F: for<'a> Fn(AioTransaction<'a>) -> MigrationFuture<'a> + Send + Sync + 'static {
+          return MigrationStep {
+               version: version,
+               name: name.to_string(),
+               up: MigrationAction::Function(Arc::new(up)),
+               down: None
+          };
+     }
+
+     /// A step executing one or more SQL statements separated by `;`. Its checksum covers the SQL.
+     pub fn sql(version: u32, name: &str, up: &str) -> MigrationStep {
+          return MigrationStep {
+               version: version,
+               name: name.to_string(),
+               up: MigrationAction::Sql(up.to_string()),
+               down: None
+          };
+     }
+
+     /// Sets the closure reverting the step, run by `AioDatabase::migrate_down`.
+     pub fn down<F>(mut self, down: F) -> MigrationStep
+     where F: for<'a> Fn(AioTransaction<'a>) -> MigrationFuture<'a> + Send + Sync + 'static {
+          self.down = Some(MigrationAction::Function(Arc::new(down)));
+          return self;
+     }
+
+     /// Sets the SQL reverting the step, run by `AioDatabase::migrate_down`.
+     pub fn down_sql(mut self, down: &str) -> MigrationStep {
+          self.down = Some(MigrationAction::Sql(down.to_string()));
+          return self;
+     }
+
+     pub fn version(&self) -> u32 {
+          return self.version;
+     }
+
+     pub fn name(&self) -> &str {
+          return self.name.as_str();
+     }
+
+     /// The checksum recorded in `_aio_migrations`, a FNV-1a hash of the version, the name and the SQL of the up action.
+     pub fn checksum(&self) -> String {
+          let mut hash: u64 = 0xcbf29ce484222325;
+
+          let mut write = |bytes: &[u8]| {
+               for byte in bytes {
+                    hash ^= *byte as u64;
+                    hash = hash.wrapping_mul(0x100000001b3);
+               }
+          };
+
+          write(&self.version.to_le_bytes());
+          write(self.name.as_bytes());
+
+          if let MigrationAction::Sql(sql) = &self.up {
+               write(&[0]);
+               write(sql.as_bytes());
+          }
+
+          return format!("{:016x}", hash);
+     }
+}
+
+/// Describes how the table of a model is migrated, passed to `create_with_migrator` / `create_in_memory_with_migrator`.
+///
+/// The table is first migrated to the model according to the `MigrationPolicy`, then the pending `MigrationStep`s run in order of
+/// their version, each in its own transaction together with its record in `_aio_migrations`.
+/// ```rust
+/// let migrator = Migrator::new(MigrationPolicy::AdditiveOnly)
+///     .convert_column("price", |value| match value {
+///         SqlValue::Real(price) => Ok(SqlValue::Integer((price * 100.0) as i64)),
+///         value => Ok(value)
+///     })
+///     .step(MigrationStep::sql(1, "discount_old_products", "UPDATE Product SET price = price / 2 WHERE id < 100"));
+///
+/// let (db, report) = AioDatabase::create_with_migrator::<Product>("G:\\".into(), "Test".into(), migrator).await?;
+/// ```
+#[derive(Clone, Default)]
+pub struct Migrator {
+     pub(crate) policy: MigrationPolicy,
+     pub(crate) converters: HashMap<String, ColumnConverter>,
+     /// The callbacks and the type of the model they were registered for.
+     pub(crate) backfills: HashMap<String, (TypeId, ColumnBackfill)>,
+     /// Ordered by version.
+     pub(crate) upgrades: Vec<ModelUpgrade>,
+     pub(crate) steps: Vec<MigrationStep>
+}
+
+impl Migrator {
+     pub fn new(policy: MigrationPolicy) -> Migrator {
+          return Migrator {
+               policy: policy,
+               ..Default::default()
+          };
+     }
+
+     pub fn policy(&self) -> MigrationPolicy {
+          return self.policy;
+     }
+
+     /// Converts the values of a column whose type changed with `converter`, instead of `CAST(column AS type)`.
+     pub fn convert_column<F>(mut self, column_name: &str, converter: F) -> Migrator
+     where F: Fn(Value) -> Result<Value, AioError> + Send + Sync + 'static {
+          self.converters.insert(column_name.to_string(), Arc::new(converter));
+          return self;
+     }
+
+     /// Fills a column added by the migration with the value returned by `backfill` for each existing row, read as **T**,
+     /// instead of the column `DEFAULT`. Runs once, in a transaction, when the column is added.
+     /// ```rust
+     /// let migrator = Migrator::new(MigrationPolicy::AdditiveOnly)
+     ///     .backfill("display_name", |person: &Person| format!("{} {}", person.first_name, person.last_name));
+     /// ```
+     pub fn backfill<T, V, F>(mut self, column_name: &str, backfill: F) -> Migrator
+     where T: 'static, V: ToSql, F: Fn(&T) -> V + Send + Sync + 'static {
+          let name = column_name.to_string();
+
+          let backfill: ColumnBackfill = Arc::new(move |row: &dyn Any| {
+               let row = row.downcast_ref::<T>()
+                    .ok_or_else(|| AioError::SchemaMismatch(format!("The backfill of column {} was registered for another model.", name)))?;
+               to_sql_value(&backfill(row))
+          });
+
+          self.backfills.insert(column_name.to_string(), (TypeId::of::<T>(), backfill));
+
+          return self;
+     }
+
+     /// Upgrades a table at a model version below `version` by reading every row as **Old**, converting it with `upgrade` and
+     /// rewriting the table with the columns of **New**, in a single transaction. The version of the model is set with `#[aio(version = N)]`
+     /// and recorded per table. Upgrades run in order of their version, before the table is migrated to the model.
+     /// ```rust
+     /// let migrator = Migrator::new(MigrationPolicy::AdditiveOnly)
+     ///     .upgrade(2, |old: PersonV1| Person {
+     ///         first_name: old.name.split(' ').next().unwrap_or_default().into(),
+     ///         last_name: old.name.split(' ').nth(1).unwrap_or_default().into(),
+     ///         ..Default::default()
+     ///     });
+     /// ```
+     pub fn upgrade<Old, New, F>(mut self, version: u32, upgrade: F) -> Migrator
+     where Old: Default + Struct + Clone + AioModel, New: Default + Struct + Clone + AioModel, F: Fn(Old) -> New + Send + Sync + 'static {
+          let upgrade: ModelUpgradeFn = Arc::new(move |conn: &rusqlite::Connection, name: &str| upgrade_rows::<Old, New, F>(conn, name, &upgrade));
+
+          self.upgrades.retain(|x| x.version != version);
+          let position = self.upgrades.partition_point(|x| x.version < version);
+          self.upgrades.insert(position, ModelUpgrade { version: version, upgrade: upgrade });
+
+          return self;
+     }
+
+     /// Registers a versioned step. Steps run in order of their version, whatever the order they were registered in.
+     pub fn step(mut self, step: MigrationStep) -> Migrator {
+          let position = self.steps.partition_point(|x| x.version <= step.version);
+          self.steps.insert(position, step);
+          return self;
+     }
+
+     /// The registered steps, ordered by version.
+     pub fn steps(&self) -> &[MigrationStep] {
+          return &self.steps;
+     }
+}
@@ -0,0 +1,250 @@
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rusqlite::OpenFlags;
+use tokio_rusqlite::Connection as SqliteConnection;
+
+use super::aio_database::{AioDatabase, AioDatabaseConnection};
+use super::aio_error::AioError;
+use super::internal::pool::{ReaderSettings, DEFAULT_READER_POOL_SIZE};
+use super::internal::queries::apply_database_options;
+use super::models::RetryPolicy;
+use super::{AutoVacuum, JournalMode, Synchronous, TempStore};
+
+/// Where and how a database is opened, and the settings applied to it.
+/// ### Example
+/// ```rust
+/// let db = AioDatabaseOptions::new("G:\\App.db")
+///     .journal_mode(JournalMode::Wal)
+///     .synchronous(Synchronous::Full)
+///     .busy_timeout(Duration::from_secs(10))
+///     .cache_size(-64000)
+///     .open().await?;
+///
+/// db.register::<Person>().await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct AioDatabaseOptions {
+     pub(crate) location: DatabaseLocation,
+     pub(crate) read_only: bool,
+     pub(crate) create: bool,
+     pub(crate) journal_mode: JournalMode,
+     pub(crate) synchronous: Synchronous,
+     pub(crate) busy_timeout: Duration,
+     pub(crate) cache_size: Option<i64>,
+     pub(crate) mmap_size: Option<u64>,
+     pub(crate) page_size: Option<u32>,
+     pub(crate) auto_vacuum: AutoVacuum,
+     pub(crate) temp_store: TempStore,
+     pub(crate) journal_size_limit: Option<i64>,
+     pub(crate) reader_pool_size: usize,
+     pub(crate) retry_policy: Option<RetryPolicy>
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum DatabaseLocation {
+     Path(PathBuf),
+     Uri(String),
+     Memory
+}
+
+impl AioDatabaseOptions {
+     /// A database file, created with its parent directories if it doesn't exist.
+     pub fn new(path: impl AsRef<Path>) -> AioDatabaseOptions {
+          return AioDatabaseOptions::with_location(DatabaseLocation::Path(path.as_ref().to_path_buf()));
+     }
+
+     /// A database opened from a SQLite URI, e.g. `file:data.db?cache=shared`.
+     pub fn uri(uri: &str) -> AioDatabaseOptions {
+          return AioDatabaseOptions::with_location(DatabaseLocation::Uri(uri.to_string()));
+     }
+
+     /// An in-memory database.
+     pub fn in_memory() -> AioDatabaseOptions {
+          return AioDatabaseOptions::with_location(DatabaseLocation::Memory);
+     }
+
+     fn with_location(location: DatabaseLocation) -> AioDatabaseOptions {
+          return AioDatabaseOptions {
+               location: location,
+               read_only: false,
+               create: true,
+               journal_mode: JournalMode::default(),
+               synchronous: Synchronous::default(),
+               busy_timeout: Duration::from_secs(5),
+               cache_size: None,
+               mmap_size: None,
+               page_size: None,
+               auto_vacuum: AutoVacuum::default(),
+               temp_store: TempStore::default(),
+               journal_size_limit: None,
+               reader_pool_size: DEFAULT_READER_POOL_SIZE,
+               retry_policy: None
+          };
+     }
+
+     /// Opens the database read only, the file must exist. Settings stored in the file (journal mode, page size, auto_vacuum) are left as they are.
+     pub fn read_only(mut self, read_only: bool) -> AioDatabaseOptions {
+          self.read_only = read_only;
+          return self;
+     }
+
+     /// Whether a missing database file is created, default is true. Without it opening a missing file fails with `AioError::Io`.
+     pub fn create(mut self, create: bool) -> AioDatabaseOptions {
+          self.create = create;
+          return self;
+     }
+
+     pub fn journal_mode(mut self, journal_mode: JournalMode) -> AioDatabaseOptions {
+          self.journal_mode = journal_mode;
+          return self;
+     }
+
+     pub fn synchronous(mut self, synchronous: Synchronous) -> AioDatabaseOptions {
+          self.synchronous = synchronous;
+          return self;
+     }
+
+     /// How long a statement waits for a lock held by another connection before failing with `AioError::Busy`, default is 5 seconds.
+     /// Every attempt of the retry policy waits this long first. Without `retry_policy` the database uses `RetryPolicy::none()`, or `RetryPolicy::default()` if `busy_timeout` is 0.
+     pub fn busy_timeout(mut self, busy_timeout: Duration) -> AioDatabaseOptions {
+          self.busy_timeout = busy_timeout;
+          return self;
+     }
+
+     /// `PRAGMA cache_size`, pages if positive, KiB if negative.
+     pub fn cache_size(mut self, cache_size: i64) -> AioDatabaseOptions {
+          self.cache_size = Some(cache_size);
+          return self;
+     }
+
+     /// `PRAGMA mmap_size` in bytes, 0 disables memory mapped I/O.
+     pub fn mmap_size(mut self, mmap_size: u64) -> AioDatabaseOptions {
+          self.mmap_size = Some(mmap_size);
+          return self;
+     }
+
+     /// `PRAGMA page_size`, a power of two between 512 and 65536. Only applied when the database file is created.
+     pub fn page_size(mut self, page_size: u32) -> AioDatabaseOptions {
+          self.page_size = Some(page_size);
+          return self;
+     }
+
+     pub fn auto_vacuum(mut self, auto_vacuum: AutoVacuum) -> AioDatabaseOptions {
+          self.auto_vacuum = auto_vacuum;
+          return self;
+     }
+
+     /// `PRAGMA temp_store`, where temporary tables and indexes are stored, default is `TempStore::Memory`. Also applied to the readers.
+     pub fn temp_store(mut self, temp_store: TempStore) -> AioDatabaseOptions {
+          self.temp_store = temp_store;
+          return self;
+     }
+
+     /// `PRAGMA journal_size_limit` in bytes, the size the journal or WAL file is truncated to, -1 for no limit. Left at the SQLite default if not set.
+     pub fn journal_size_limit(mut self, journal_size_limit: i64) -> AioDatabaseOptions {
+          self.journal_size_limit = Some(journal_size_limit);
+          return self;
+     }
+
+     /// How many read-only connections a file database in WAL mode opens, default is 4. See `AioDatabase::set_reader_pool_size`.
+     pub fn reader_pool_size(mut self, reader_pool_size: usize) -> AioDatabaseOptions {
+          self.reader_pool_size = reader_pool_size;
+          return self;
+     }
+
+     /// How inserts, updates and deletes are retried while the database is busy or locked, see `busy_timeout` for the default. See `AioDatabase::set_retry_policy`.
+     pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> AioDatabaseOptions {
+          self.retry_policy = Some(retry_policy);
+          return self;
+     }
+
+     /// Opens the database and applies the settings. Models are added with `AioDatabase::register`.
+     pub async fn open(self) -> Result<AioDatabase, AioError> {
+          let retry_policy = self.retry_policy;
+          let mut db = AioDatabase::new(self.connect().await?);
+
+          if let Some(retry_policy) = retry_policy {
+               db.set_retry_policy(retry_policy);
+          }
+
+          db.conn.open_readers().await?;
+
+          return Ok(db);
+     }
+
+     /// Opens the writer connection and applies the settings, without the readers.
+     pub(crate) async fn connect(self) -> Result<AioDatabaseConnection, AioError> {
+          if let Some(page_size) = self.page_size
+               && (!page_size.is_power_of_two() || !(512..=65536).contains(&page_size)) {
+               return Err(AioError::Other(format!("Invalid page size {}, it must be a power of two between 512 and 65536.", page_size)));
+          }
+
+          let path = match &self.location {
+               DatabaseLocation::Path(path) => {
+                    if self.create && !self.read_only
+                         && let Some(parent) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+                         create_dir_all(parent)?;
+                    }
+
+                    Some(path.clone())
+               },
+               DatabaseLocation::Uri(uri) => Some(PathBuf::from(uri)),
+               DatabaseLocation::Memory => None
+          };
+
+          let sqlite_connection = match &path {
+               Some(path) => SqliteConnection::open_with_flags(path, self.flags()).await?,
+               None => SqliteConnection::open_in_memory().await?
+          };
+
+          let reader_path = match &self.location {
+               DatabaseLocation::Uri(uri) => Some(PathBuf::from(reader_uri(uri))),
+               _ => path
+          };
+
+          let reader_pool_size = self.reader_pool_size;
+          let reader_settings = self.reader_settings();
+          apply_database_options(&sqlite_connection, self).await?;
+
+          return Ok(AioDatabaseConnection::new(sqlite_connection, reader_path, reader_pool_size, reader_settings));
+     }
+
+     fn reader_settings(&self) -> ReaderSettings {
+          return ReaderSettings {
+               busy_timeout: self.busy_timeout,
+               cache_size: self.cache_size,
+               mmap_size: self.mmap_size,
+               temp_store: self.temp_store
+          };
+     }
+
+     fn flags(&self) -> OpenFlags {
+          let mode = if self.read_only {
+               OpenFlags::SQLITE_OPEN_READ_ONLY
+          } else if self.create {
+               OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE
+          } else {
+               OpenFlags::SQLITE_OPEN_READ_WRITE
+          };
+
+          return mode | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+     }
+}
+
+/// The URI the readers open. SQLite refuses a `mode` less restrictive than the read-only flags, so `mode=rw` / `mode=rwc` become `mode=ro`.
+fn reader_uri(uri: &str) -> String {
+     let Some((file, query)) = uri.split_once('?') else {
+          return uri.to_string();
+     };
+
+     let params: Vec<&str> = query.split('&')
+          .map(|x| match x {
+               "mode=rw" | "mode=rwc" => "mode=ro",
+               x => x
+          })
+          .collect();
+
+     return format!("{}?{}", file, params.join("&"));
+}
@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use rusqlite::types::FromSql;
+use rusqlite::ToSql;
+
+use super::aio_database::AioDatabase;
+use super::aio_error::AioError;
+use super::internal::pool::ReaderSettings;
+use super::internal::queries::{get_journal_mode, get_pragma, set_pragma};
+use super::models::DatabaseSettings;
+use super::{AutoVacuum, LockingMode, Synchronous, TempStore};
+
+/// Reads and sets the pragmas of a database, returned by `AioDatabase::pragmas`. Each call waits for open transactions.
+/// `cache_size`, `mmap_size`, `temp_store` and `busy_timeout` apply to the writer and every reader, the others to the writer.
+/// ### Example
+/// ```rust
+/// let pragmas = file_db.pragmas();
+///
+/// pragmas.set_cache_size(-64000).await?;
+/// pragmas.set_foreign_keys(true).await?;
+///
+/// assert_eq!(pragmas.cache_size().await?, -64000);
+/// ```
+pub struct Pragmas<'a> {
+     db: &'a AioDatabase
+}
+
+impl<'a> Pragmas<'a> {
+     pub(crate) fn new(db: &'a AioDatabase) -> Pragmas<'a> {
+          return Pragmas {
+               db: db
+          };
+     }
+
+     /// `PRAGMA cache_size`, pages if positive, KiB if negative.
+     pub async fn cache_size(&self) -> Result<i64, AioError> {
+          return self.get("cache_size").await;
+     }
+
+     pub async fn set_cache_size(&self, cache_size: i64) -> Result<(), AioError> {
+          return self.set_shared(|settings| settings.cache_size = Some(cache_size)).await;
+     }
+
+     /// `PRAGMA mmap_size` in bytes, 0 if memory mapped I/O is disabled.
+     pub async fn mmap_size(&self) -> Result<u64, AioError> {
+          let mmap_size: i64 = self.get("mmap_size").await?;
+          return Ok(mmap_size.max(0) as u64);
+     }
+
+     pub async fn set_mmap_size(&self, mmap_size: u64) -> Result<(), AioError> {
+          return self.set_shared(|settings| settings.mmap_size = Some(mmap_size)).await;
+     }
+
+     /// `PRAGMA temp_store`, where temporary tables and indexes are stored.
+     pub async fn temp_store(&self) -> Result<TempStore, AioError> {
+          let temp_store = self.get("temp_store").await?;
+          return parse("temp_store", temp_store, TempStore::from_pragma(temp_store));
+     }
+
+     pub async fn set_temp_store(&self, temp_store: TempStore) -> Result<(), AioError> {
+          return self.set_shared(|settings| settings.temp_store = temp_store).await;
+     }
+
+     /// `PRAGMA foreign_keys`, whether foreign key constraints are enforced.
+     pub async fn foreign_keys(&self) -> Result<bool, AioError> {
+          return self.get("foreign_keys").await;
+     }
+
+     pub async fn set_foreign_keys(&self, foreign_keys: bool) -> Result<(), AioError> {
+          return self.set("foreign_keys", foreign_keys).await;
+     }
+
+     /// `PRAGMA busy_timeout`, how long a statement waits for a lock held by another connection before failing with `AioError::Busy`.
+     pub async fn busy_timeout(&self) -> Result<Duration, AioError> {
+          let busy_timeout: i64 = self.get("busy_timeout").await?;
+          return Ok(Duration::from_millis(busy_timeout.max(0) as u64));
+     }
+
+     /// The retry policy of the handles is left as it is, see `RetryPolicy`.
+     pub async fn set_busy_timeout(&self, busy_timeout: Duration) -> Result<(), AioError> {
+          return self.set_shared(|settings| settings.busy_timeout = busy_timeout).await;
+     }
+
+     /// `PRAGMA wal_autocheckpoint`, the WAL size in pages that triggers a checkpoint, 0 if automatic checkpoints are disabled.
+     pub async fn wal_autocheckpoint(&self) -> Result<u32, AioError> {
+          let wal_autocheckpoint: i64 = self.get("wal_autocheckpoint").await?;
+          return Ok(wal_autocheckpoint.max(0) as u32);
+     }
+
+     pub async fn set_wal_autocheckpoint(&self, pages: u32) -> Result<(), AioError> {
+          return self.set("wal_autocheckpoint", pages).await;
+     }
+
+     /// `PRAGMA journal_size_limit` in bytes, the size the journal or WAL file is truncated to, -1 for no limit.
+     pub async fn journal_size_limit(&self) -> Result<i64, AioError> {
+          return self.get("journal_size_limit").await;
+     }
+
+     pub async fn set_journal_size_limit(&self, journal_size_limit: i64) -> Result<(), AioError> {
+          return self.set("journal_size_limit", journal_size_limit).await;
+     }
+
+     /// `PRAGMA locking_mode` of the writer.
+     pub async fn locking_mode(&self) -> Result<LockingMode, AioError> {
+          let locking_mode: String = self.get("locking_mode").await?;
+          return parse("locking_mode", &locking_mode, LockingMode::from_pragma(&locking_mode));
+     }
+
+     /// Sets `PRAGMA locking_mode` and returns the mode SQLite switched to. **Exclusive** closes the readers, reads run on the writer,
+     /// and **Normal** reopens them in WAL mode.
+     pub async fn set_locking_mode(&self, locking_mode: LockingMode) -> Result<LockingMode, AioError> {
+          let conn = &self.db.conn;
+          let _guard = conn.lock().await?;
+
+          conn.close_readers().await?;
+
+          // The mode takes effect (taking or releasing the locks) the next time the database is read
+          let result = conn.sqlite_connection.call(move |conn| {
+               let locking_mode = conn.pragma_update_and_check(None, "locking_mode", locking_mode.to_string(), |row| row.get::<_, String>(0))?;
+               conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+               Ok(locking_mode)
+          }).await;
+
+          conn.open_readers().await?;
+
+          let locking_mode = result?;
+          return parse("locking_mode", &locking_mode, LockingMode::from_pragma(&locking_mode));
+     }
+
+     /// The current value of every setting of the writer.
+     pub async fn snapshot(&self) -> Result<DatabaseSettings, AioError> {
+          let conn = &self.db.conn;
+          let _guard = conn.lock().await?;
+          let connection = &conn.sqlite_connection;
+
+          let synchronous = get_pragma(connection, "synchronous").await?;
+          let auto_vacuum = get_pragma(connection, "auto_vacuum").await?;
+          let temp_store = get_pragma(connection, "temp_store").await?;
+          let locking_mode: String = get_pragma(connection, "locking_mode").await?;
+          let page_size: i64 = get_pragma(connection, "page_size").await?;
+          let mmap_size: i64 = get_pragma(connection, "mmap_size").await?;
+          let busy_timeout: i64 = get_pragma(connection, "busy_timeout").await?;
+          let wal_autocheckpoint: i64 = get_pragma(connection, "wal_autocheckpoint").await?;
+
+          return Ok(DatabaseSettings {
+               journal_mode: get_journal_mode(connection).await?,
+               synchronous: parse("synchronous", synchronous, Synchronous::from_pragma(synchronous))?,
+               locking_mode: parse("locking_mode", &locking_mode, LockingMode::from_pragma(&locking_mode))?,
+               auto_vacuum: parse("auto_vacuum", auto_vacuum, AutoVacuum::from_pragma(auto_vacuum))?,
+               temp_store: parse("temp_store", temp_store, TempStore::from_pragma(temp_store))?,
+               page_size: page_size.max(0) as u32,
+               cache_size: get_pragma(connection, "cache_size").await?,
+               mmap_size: mmap_size.max(0) as u64,
+               busy_timeout: Duration::from_millis(busy_timeout.max(0) as u64),
+               foreign_keys: get_pragma(connection, "foreign_keys").await?,
+               wal_autocheckpoint: wal_autocheckpoint.max(0) as u32,
+               journal_size_limit: get_pragma(connection, "journal_size_limit").await?
+          });
+     }
+
+     async fn get<T: FromSql + Send + 'static>(&self, name: &'static str) -> Result<T, AioError> {
+          let _guard = self.db.conn.lock().await?;
+
+          return get_pragma(&self.db.conn.sqlite_connection, name).await;
+     }
+
+     async fn set<V: ToSql + Send + 'static>(&self, name: &'static str, value: V) -> Result<(), AioError> {
+          let _guard = self.db.conn.lock().await?;
+
+          return set_pragma(&self.db.conn.sqlite_connection, name, value).await;
+     }
+
+     /// Changes a setting shared by the writer and the readers, applied to the open connections and to the readers opened later.
+     async fn set_shared(&self, update: impl FnOnce(&mut ReaderSettings)) -> Result<(), AioError> {
+          let conn = &self.db.conn;
+          let _guard = conn.lock().await?;
+
+          let settings = {
+               let mut settings = conn.reader_settings.write().unwrap();
+               update(&mut settings);
+               *settings
+          };
+
+          conn.sqlite_connection.call(move |conn| Ok(settings.apply(conn)?)).await?;
+
+          if let Some(pool) = conn.reader_pool() {
+               pool.apply(settings).await?;
+          }
+
+          return Ok(());
+     }
+}
+
+fn parse<T>(name: &str, value: impl std::fmt::Display, parsed: Option<T>) -> Result<T, AioError> {
+     return parsed.ok_or_else(|| AioError::Other(format!("Unknown {} {}.", name, value)));
+}
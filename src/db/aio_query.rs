@@ -1,400 +1,536 @@
-use core::str;
-
-use bevy_reflect::{Reflect, ReflectMut, Struct};
-use tokio_rusqlite::{Connection, Row};
-
-use super::{aio_database::AioDatabase, internal::queries::{generate_get_query, generate_where_query}};
-
-/// Used for building a SQL query through a simple Rust API for querying AioDatabase.
-/// ### Example
-/// ```rust
-/// let query  = QueryBuilder::new(&file_db)
-///     .field("name")
-///     .where_is(Operator::Eq("Mylo".into()), None);
-/// ```
-pub struct QueryBuilder<'a> {
-     pub table_name: String,
-     pub query_options: Vec<QueryOption<'a>>,
-     pub db: &'a AioDatabase
-}
-
-unsafe impl<'a> Send for QueryBuilder<'a> { }
-
-/// Part of QueryBuilder's API for generating query.
-/// ### Example
-/// ```rust
-/// let query  = QueryBuilder::new(&file_db)
-///     .field("name")
-///     .where_is(Operator::Eq("Mylo".into()), None);
-/// ```
-pub struct QueryOption<'a> {
-     pub field_name: String,
-     pub operator: Option<Operator>,
-     pub next: Option<Next>,
-     query_builder: Option<&'a QueryBuilder<'a>>
-}
-
-unsafe impl<'a> Send for QueryOption<'a> { }
-
-/// # Inspired by OData filter queries.
-/// - **Eq** = Equal
-/// - **Ne** = Not equal
-/// - **Gt** = Greater Than
-/// - **Lt** = Less Than
-/// - **Ge** = Greater or Equal
-/// - **Le** = Less or Equal
-#[derive(Debug, Clone, PartialEq)]
-pub enum Operator {
-     Eq(String),
-     Ne(String),
-     Gt(String),
-     Lt(String),
-     Ge(String),
-     Le(String),
-     Contains(String),
-     StartsWith(String),
-     EndsWith(String)
-}
-
-/// Use this for declaring what the next query filter will be if any (**AND** or **OR**).
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Next {
-     And,
-     Or
-}
-
-impl QueryBuilder<'_> {
-     /// Create a new instance of a QueryBuilder, used for querying 
-     pub fn new<'a>(db: &'a AioDatabase) -> QueryBuilder<'a> {
-          return QueryBuilder {
-               table_name: db.get_name().to_string(),
-               query_options: Vec::default(),
-               db: db
-          }
-     }
-
-     /// Declare which **field (column)** you want to query.
-     /// ```rust
-     /// let query_options  = QueryBuilder::new(&file_db)
-     ///     .field("name");
-     /// ```
-     pub fn field<'a>(&'a self, name: &str) -> QueryOption<'a> {
-          QueryOption {
-               field_name: name.into(), 
-               operator: None,
-               query_builder: Some(self),
-               next: Some(Next::And)
-          }
-     }
-
-     /// Clears out all query options
-     pub fn clear(&mut self) {
-          self.query_options.clear();
-     }
-
-     /// Return the first **value (row)** that matched or **None** if there are not query matches. 
-     pub async fn get_single_value<'a, T: Default + Struct + Clone>(self) -> Option<T> {
-          let db = self.db;
-          let query = generate_get_query::<T>(&self);
-          return db.get_single_value::<T>(query).await;
-     }
-
-     /// Return the all **values (rows)** that matched or **None** if there are not query matches. 
-     pub async fn get_many_values<'a, T: Default + Struct + Clone>(self) -> Option<Vec<T>> {
-          let db = self.db;
-          let query = generate_get_query::<T>(&self);
-          return db.get_many_values::<T>(query).await;
-     }
-
-     /// Updates **all values** that matches the query filter with values of the struct of type **T**. Returns a Result of the number of rows affected or error if update was unsuccessful.
-     pub async fn update_value<'a, T: Default + Struct + Clone>(self, value: T)  -> Result<usize, String> {
-          let db = self.db;
-          let where_query = generate_where_query::<T>(&self);
-          return db.update_value::<T>(value, where_query).await;
-     }
-
-     /// Updates concurrently **all values** that matches the query filter with values of the struct of type **T**. Returns a Result of the number of rows affected or error if update was unsuccessful.
-     pub async fn update_value_concurrent<'a, T: Default + Struct + Clone>(self, value: T)  -> Result<usize, String> {
-          let db = self.db;
-          let where_query = generate_where_query::<T>(&self);
-          return db.update_value_concurrent::<T>(value, where_query).await;
-     }
-
-     /// Updates specific field / column that matches the query filter. Returns a Result of the number of rows affected or error if update was unsuccessful.
-     pub async fn partial_update<'a, T: Default + Struct + Clone>(self, field_name: String, field_value: String)  -> Result<usize, String> {
-          let db = self.db;
-          let where_query = generate_where_query::<T>(&self);
-          return db.partial_update::<T>(field_name, field_value, where_query).await;
-     }
-
-     /// Updates concurrently specific field / column that matches the query filter. Returns a Result of the number of rows affected or error if update was unsuccessful.
-     pub async fn partial_update_concurrent<'a, T: Default + Struct + Clone>(self, field_name: String, field_value: String)  -> Result<usize, String> {
-          let db = self.db;
-          let where_query = generate_where_query::<T>(&self);
-          return db.partial_update_concurrent::<T>(field_name, field_value, where_query).await;
-     }
-
-     /// Deletes **all values** that match the query filter. Returns a Result of the number of rows affected or error if update was unsuccessful.
-     pub async fn delete_value<'a, T: Default + Struct + Clone>(self) -> Result<usize, String> {
-          let db = self.db;
-          let where_query = generate_where_query::<T>(&self);
-          return db.delete_value::<T>(where_query).await;
-     }
-
-     /// Returns if any value / row matches the the query filter.
-     pub async fn any<'a, T: Default + Struct + Clone>(self) -> bool {
-          let db = self.db;
-          let where_query = generate_where_query::<T>(&self);
-          return db.any::<T>(where_query).await;
-     }
-
-     /// Returns the count of values / rows that match the the query filter.
-     pub async fn count<'a, T: Default + Struct + Clone>(self) -> u64 {
-          let db = self.db;
-          let where_query = generate_where_query::<T>(&self);
-          return db.count::<T>(where_query).await;
-     }
-
-     /// Returns if all rows / records match the the query filter.
-     pub async fn all<'a, T: Default + Struct + Clone>(self) -> bool {
-          let db = self.db;
-          let where_query = generate_where_query::<T>(&self);
-          return db.all::<T>(where_query).await;
-     }
-}
-
-impl QueryOption<'_> {
-
-     /// Define the Operator and it's value which will be used for the **WHERE** clause, and the Next which will be used for chaining with the next clause. by default None is equal to **Next::And** if there are more than 1 clauses.
-     pub fn where_is<'a>(&'a self, operator: Operator, next: Option<Next>) -> QueryBuilder<'a> {
-          let mut query = QueryBuilder {
-               table_name: self.query_builder.unwrap().table_name.clone(),
-               query_options: self.query_builder.unwrap().query_options.iter().map(|x| QueryOption {
-                    field_name: x.field_name.clone(),
-                    operator: x.operator.clone(),
-                    query_builder: x.query_builder,
-                    next: x.next.clone()
-               }).collect(),
-               db: self.query_builder.unwrap().db
-          };
-
-          if let Some(next) = next  {
-               query.query_options.push(QueryOption {
-                    field_name: self.field_name.clone(),
-                    operator: Some(operator),
-                    query_builder: None,
-                    next: Some(next)
-               });
-          }
-          else {
-               query.query_options.push(QueryOption {
-                    field_name: self.field_name.clone(),
-                    operator: Some(operator),
-                    query_builder: None,
-                    next: Some(Next::And)
-               });
-          }
-          
-          return query;
-     }
-}
-
-pub(crate) struct QueryRowResult<T> {
-     pub value: Option<T>,
-}
-
-impl<T: Default + Struct + Clone> QueryRowResult<T> {
-     pub(crate) async fn new(
-          query: String, 
-          connection: &Connection) -> Option<QueryRowResult<T>> { 
-          
-          let result = connection.call(move |conn| {
-               let mut stmt = conn.prepare(&query)?;
-               let mut rows = stmt.query([])?;
-               
-               if let Some(row) = rows.next()? {
-                    let mapped_value = map_row_to_struct::<T>(row)?;
-                    Ok(Some(mapped_value))
-               } else {
-                    Ok(None)
-               }
-          }).await;
-          
-          match result {
-               Ok(value) => Some(QueryRowResult { value }),
-               Err(_) => None
-          }
-     }
-}
-
-pub(crate) struct QueryRowsResult<T> {
-     pub value: Option<Vec<Result<T, Error>>>,
-}
-
-impl<T: Default + Struct + Clone> QueryRowsResult<T> {
-     pub(crate) async fn new_many(
-          query: String, 
-          connection: &Connection) -> Option<QueryRowsResult<T>> {
-          
-          let result = connection.call(move |conn| {
-               let mut stmt = conn.prepare(&query)?;
-               let rows = stmt.query_map([], |row| {
-                    Ok(map_row_to_struct::<T>(row))
-               })?;
-               
-               let mut results = Vec::new();
-               
-               for row_result in rows {
-                    match row_result {
-                         Ok(mapped_value) => results.push(mapped_value),
-                         Err(e) => return Err(e.into())
-                    }
-               }
-               
-               if results.is_empty() {
-                    Ok(None)
-               } else {
-                    Ok(Some(results))
-               }
-          }).await;
-          
-          match result {
-               Ok(value) => Some(QueryRowsResult { value }),
-               Err(_) => None
-          }
-     }
-}
-
-#[derive(Default, Reflect, Clone)]
-pub(crate) struct AnyCountResult {
-     pub count_total: u64
-}
-
-use bevy_reflect::GetField;
-use tokio_rusqlite::Error;
-
-// Helper function to map rusqlite::Row to Bevy Struct using reflection
-fn map_row_to_struct<T: Default + Struct + Clone>(row: &Row) -> Result<T, Error> {
-     let mut instance = T::default();
-
-     let mut struct_mut2: Box<dyn Struct> = Box::new(T::default());
-     let ReflectMut::Struct(reflected2) = struct_mut2.reflect_mut() else { unreachable!() };
-     let struct_immutable: Box<dyn Struct> = Box::new(T::default());
-
-     for (index, field) in struct_immutable.iter_fields().enumerate() {
-          let field_type = field.reflect_type_ident().unwrap();
-          let field_name = reflected2.name_at(index).clone().unwrap();
-
-          // Try to get the value from the row by field name first, then by index
-          match field_type {
-               "bool" => {
-                    let value: Result<i32, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         let bool_value = sql_value != 0;
-                         *instance.get_field_mut::<bool>(field_name).unwrap() = bool_value;
-                    }
-               },
-               "u8" => {
-                    let value: Result<i64, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<u8>(field_name).unwrap() = sql_value as u8;
-                    }
-               },
-               "u16" => {
-                    let value: Result<i64, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<u16>(field_name).unwrap() = sql_value as u16;
-                    }
-               },
-               "u32" => {
-                    let value: Result<i64, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<u32>(field_name).unwrap() = sql_value as u32;
-                    }
-               },
-               "u64" => {
-                    let value: Result<i64, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<u64>(field_name).unwrap() = sql_value as u64;
-                    }
-               },
-               "i8" => {
-                    let value: Result<i64, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<i8>(field_name).unwrap() = sql_value as i8;
-                    }
-               },
-               "i16" => {
-                    let value: Result<i64, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<i16>(field_name).unwrap() = sql_value as i16;
-                    }
-               },
-               "i32" => {
-                    let value: Result<i32, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<i32>(field_name).unwrap() = sql_value;
-                    }
-               },
-               "i64" => {
-                    let value: Result<i64, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<i64>(field_name).unwrap() = sql_value;
-                    }
-               },
-               "f32" => {
-                    let value: Result<f64, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<f32>(field_name).unwrap() = sql_value as f32;
-                    }
-               },
-               "f64" => {
-                    let value: Result<f64, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         *instance.get_field_mut::<f64>(field_name).unwrap() = sql_value;
-                    }
-               },
-               "char" => {
-                    let value: Result<String, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         let char_value = sql_value.chars().next().unwrap_or(' ');
-                         *instance.get_field_mut::<char>(field_name).unwrap() = char_value;
-                    }
-               },
-               "String" => {
-                    let value: Result<String, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = value {
-                         // Create buffer similar to your existing code
-                         let mut buffer: Vec<u8> = Vec::new();
-                         buffer.extend_from_slice(sql_value.as_bytes());
-                         let string_value = String::from_utf8_lossy(&buffer).into_owned();
-                         *instance.get_field_mut::<String>(field_name).unwrap() = string_value;
-                    }
-               },
-               "Vec" => {
-                    // Handle Vec<u8> stored as BLOB or hex string
-                    let blob_result: Result<Vec<u8>, _> = row.get(field_name).or_else(|_| row.get(index));
-                    if let Ok(sql_value) = blob_result {
-                         let mut buffer: Vec<u8> = Vec::new();
-                         buffer.extend_from_slice(&sql_value);
-                         *instance.get_field_mut::<Vec<u8>>(field_name).unwrap() = buffer;
-                    } else {
-                         // Try as hex string if BLOB retrieval fails
-                         let string_result: Result<String, _> = row.get(field_name).or_else(|_| row.get(index));
-                         if let Ok(hex_string) = string_result {
-                              if let Ok(decoded) = hex::decode(&hex_string) {
-                                   let mut buffer: Vec<u8> = Vec::new();
-                                   buffer.extend_from_slice(&decoded);
-                                   *instance.get_field_mut::<Vec<u8>>(field_name).unwrap() = buffer;
-                              }
-                         }
-                    }
-               },
-               _ => {
-                    panic!("{} type not supported.", field_type);
-               }
-          }
-     }
-
-    Ok(instance)
+use core::str;
+use std::sync::Arc;
+
+use bevy_reflect::{Reflect, ReflectMut, Struct};
+use rusqlite::{params_from_iter, types::{FromSql, Value}};
+use tokio_rusqlite::Row;
+
+use super::{aio_database::AioDatabase, aio_transaction::AioTransaction, aio_error::AioError, internal::{helpers::get_field_type, pool::ReadConnection, queries::{generate_get_query, generate_where_query}}, models::{BoundQuery, ModelInfo}};
+
+/// Used for building a SQL query through a simple Rust API for querying AioDatabase.
+/// ### Example
+/// ```rust
+/// let query  = QueryBuilder::new(&file_db)
+///     .field("name")
+///     .where_is(Operator::Eq("Mylo".into()), None);
+/// ```
+pub struct QueryBuilder<'a> {
+     pub table_name: String,
+     pub query_options: Vec<QueryOption<'a>>,
+     pub db: &'a AioDatabase,
+     pub(crate) transaction: Option<&'a AioTransaction<'a>>
+}
+
+/// Part of QueryBuilder's API for generating query.
+/// ### Example
+/// ```rust
+/// let query  = QueryBuilder::new(&file_db)
+///     .field("name")
+///     .where_is(Operator::Eq("Mylo".into()), None);
+/// ```
+pub struct QueryOption<'a> {
+     pub field_name: String,
+     pub operator: Option<Operator>,
+     pub next: Option<Next>,
+     query_builder: Option<&'a QueryBuilder<'a>>
+}
+
+/// A QueryBuilder that owns a clone of the database instead of borrowing it, so it can be stored or moved into `tokio::spawn`.
+/// ### Example
+/// ```rust
+/// let query = file_db
+///     .query_owned()
+///     .field("name")
+///     .where_is(Operator::Eq("Mylo".into()), None);
+///
+/// let people = tokio::spawn(async move { query.get_many_values::<Person>().await }).await??;
+/// ```
+pub struct OwnedQueryBuilder {
+     pub table_name: String,
+     pub query_options: Vec<QueryOption<'static>>,
+     pub db: AioDatabase
+}
+
+/// Part of OwnedQueryBuilder's API for generating query.
+pub struct OwnedQueryOption {
+     pub field_name: String,
+     query_builder: OwnedQueryBuilder
+}
+
+/// # Inspired by OData filter queries.
+/// - **Eq** = Equal
+/// - **Ne** = Not equal
+/// - **Gt** = Greater Than
+/// - **Lt** = Less Than
+/// - **Ge** = Greater or Equal
+/// - **Le** = Less or Equal
+/// - **IsNull** / **IsNotNull** = the `Option` field is `None` / `Some`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operator {
+     Eq(String),
+     Ne(String),
+     Gt(String),
+     Lt(String),
+     Ge(String),
+     Le(String),
+     Contains(String),
+     StartsWith(String),
+     EndsWith(String),
+     IsNull,
+     IsNotNull
+}
+
+/// Use this for declaring what the next query filter will be if any (**AND** or **OR**).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Next {
+     And,
+     Or
+}
+
+impl QueryBuilder<'_> {
+     /// Create a new instance of a QueryBuilder, used for querying 
+     pub fn new<'a>(db: &'a AioDatabase) -> QueryBuilder<'a> {
+          return QueryBuilder {
+               table_name: db.get_name().to_string(),
+               query_options: Vec::default(),
+               db: db,
+               transaction: None
+          }
+     }
+
+     /// Declare which **field (column)** you want to query.
+     /// ```rust
+     /// let query_options  = QueryBuilder::new(&file_db)
+     ///     .field("name");
+     /// ```
+     pub fn field<'a>(&'a self, name: &str) -> QueryOption<'a> {
+          QueryOption {
+               field_name: name.into(), 
+               operator: None,
+               query_builder: Some(self),
+               next: Some(Next::And)
+          }
+     }
+
+     /// Clears out all query options
+     pub fn clear(&mut self) {
+          self.query_options.clear();
+     }
+
+     /// Return the first **value (row)** that matched or **AioError::NotFound** if there are not query matches. 
+     pub async fn get_single_value<T: Default + Struct + Clone>(self) -> Result<T, AioError> {
+          let db = self.db;
+          let query = generate_get_query::<T>(&self)?;
+          return db.get_single_value::<T>(query, self.transaction).await;
+     }
+
+     /// Return the all **values (rows)** that matched or an empty Vec if there are not query matches. 
+     pub async fn get_many_values<T: Default + Struct + Clone>(self) -> Result<Vec<T>, AioError> {
+          let db = self.db;
+          let query = generate_get_query::<T>(&self)?;
+          return db.get_many_values::<T>(query, self.transaction).await;
+     }
+
+     /// Updates **all values** that matches the query filter with values of the struct of type **T**. Returns a Result of the number of rows affected or error if update was unsuccessful.
+     pub async fn update_value<T: Default + Struct + Clone>(self, value: T)  -> Result<usize, AioError> {
+          let db = self.db;
+          let where_query = generate_where_query::<T>(&self)?;
+          return db.update_value::<T>(value, where_query, self.transaction).await;
+     }
+
+     /// Same as `update_value`. The bundled SQLite doesn't support `BEGIN CONCURRENT`, writes go through the single writer connection one at a time.
+     pub async fn update_value_concurrent<T: Default + Struct + Clone>(self, value: T)  -> Result<usize, AioError> {
+          let db = self.db;
+          let where_query = generate_where_query::<T>(&self)?;
+          return db.update_value::<T>(value, where_query, self.transaction).await;
+     }
+
+     /// Updates specific field / column that matches the query filter. Returns a Result of the number of rows affected or error if update was unsuccessful.
+     /// `field_value` is parsed as the type of the field (hex for `Vec<u8>`), a value that doesn't parse returns **AioError::SchemaMismatch**.
+     pub async fn partial_update<T: Default + Struct + Clone>(self, field_name: String, field_value: String)  -> Result<usize, AioError> {
+          let db = self.db;
+          let where_query = generate_where_query::<T>(&self)?;
+          return db.partial_update::<T>(field_name, Some(field_value), where_query, self.transaction).await;
+     }
+
+     /// Sets an `Option` field / column to NULL (None) for the rows that match the query filter. Returns the number of rows affected.
+     pub async fn partial_update_null<T: Default + Struct + Clone>(self, field_name: String) -> Result<usize, AioError> {
+          let db = self.db;
+          let where_query = generate_where_query::<T>(&self)?;
+          return db.partial_update::<T>(field_name, None, where_query, self.transaction).await;
+     }
+
+     /// Same as `partial_update`. The bundled SQLite doesn't support `BEGIN CONCURRENT`, writes go through the single writer connection one at a time.
+     pub async fn partial_update_concurrent<T: Default + Struct + Clone>(self, field_name: String, field_value: String)  -> Result<usize, AioError> {
+          let db = self.db;
+          let where_query = generate_where_query::<T>(&self)?;
+          return db.partial_update::<T>(field_name, Some(field_value), where_query, self.transaction).await;
+     }
+
+     /// Deletes **all values** that match the query filter. Returns a Result of the number of rows affected or error if update was unsuccessful.
+     pub async fn delete_value<T: Default + Struct + Clone>(self) -> Result<usize, AioError> {
+          let db = self.db;
+          let where_query = generate_where_query::<T>(&self)?;
+          return db.delete_value::<T>(where_query, self.transaction).await;
+     }
+
+     /// Returns if any value / row matches the the query filter.
+     pub async fn any<T: Default + Struct + Clone>(self) -> Result<bool, AioError> {
+          let db = self.db;
+          let where_query = generate_where_query::<T>(&self)?;
+          return db.any::<T>(where_query, self.transaction).await;
+     }
+
+     /// Returns the count of values / rows that match the the query filter.
+     pub async fn count<T: Default + Struct + Clone>(self) -> Result<u64, AioError> {
+          let db = self.db;
+          let where_query = generate_where_query::<T>(&self)?;
+          return db.count::<T>(where_query, self.transaction).await;
+     }
+
+     /// Returns if all rows / records match the the query filter.
+     pub async fn all<T: Default + Struct + Clone>(self) -> Result<bool, AioError> {
+          let db = self.db;
+          let where_query = generate_where_query::<T>(&self)?;
+          return db.all::<T>(where_query, self.transaction).await;
+     }
+}
+
+impl QueryOption<'_> {
+
+     /// Define the Operator and it's value which will be used for the **WHERE** clause, and the Next which will be used for chaining with the next clause. by default None is equal to **Next::And** if there are more than 1 clauses.
+     pub fn where_is<'a>(&'a self, operator: Operator, next: Option<Next>) -> QueryBuilder<'a> {
+          let mut query = QueryBuilder {
+               table_name: self.query_builder.unwrap().table_name.clone(),
+               query_options: self.query_builder.unwrap().query_options.iter().map(|x| QueryOption {
+                    field_name: x.field_name.clone(),
+                    operator: x.operator.clone(),
+                    query_builder: x.query_builder,
+                    next: x.next.clone()
+               }).collect(),
+               db: self.query_builder.unwrap().db,
+               transaction: self.query_builder.unwrap().transaction
+          };
+
+          if let Some(next) = next  {
+               query.query_options.push(QueryOption {
+                    field_name: self.field_name.clone(),
+                    operator: Some(operator),
+                    query_builder: None,
+                    next: Some(next)
+               });
+          }
+          else {
+               query.query_options.push(QueryOption {
+                    field_name: self.field_name.clone(),
+                    operator: Some(operator),
+                    query_builder: None,
+                    next: Some(Next::And)
+               });
+          }
+          
+          return query;
+     }
+}
+
+impl OwnedQueryBuilder {
+     /// Create a new instance of an OwnedQueryBuilder, holding a clone of `db`.
+     pub fn new(db: AioDatabase) -> OwnedQueryBuilder {
+          return OwnedQueryBuilder {
+               table_name: db.get_name().to_string(),
+               query_options: Vec::default(),
+               db: db
+          }
+     }
+
+     /// Declare which **field (column)** you want to query.
+     pub fn field(self, name: &str) -> OwnedQueryOption {
+          return OwnedQueryOption {
+               field_name: name.into(),
+               query_builder: self
+          };
+     }
+
+     /// Clears out all query options
+     pub fn clear(&mut self) {
+          self.query_options.clear();
+     }
+
+     /// Return the first **value (row)** that matched or **AioError::NotFound** if there are not query matches.
+     pub async fn get_single_value<T: Default + Struct + Clone>(self) -> Result<T, AioError> {
+          let db = self.db;
+          let query = QueryBuilder { table_name: self.table_name, query_options: self.query_options, db: &db, transaction: None };
+          return query.get_single_value::<T>().await;
+     }
+
+     /// Return the all **values (rows)** that matched or an empty Vec if there are not query matches.
+     pub async fn get_many_values<T: Default + Struct + Clone>(self) -> Result<Vec<T>, AioError> {
+          let db = self.db;
+          let query = QueryBuilder { table_name: self.table_name, query_options: self.query_options, db: &db, transaction: None };
+          return query.get_many_values::<T>().await;
+     }
+
+     /// Updates **all values** that matches the query filter with values of the struct of type **T**. Returns a Result of the number of rows affected or error if update was unsuccessful.
+     pub async fn update_value<T: Default + Struct + Clone>(self, value: T) -> Result<usize, AioError> {
+          let db = self.db;
+          let query = QueryBuilder { table_name: self.table_name, query_options: self.query_options, db: &db, transaction: None };
+          return query.update_value::<T>(value).await;
+     }
+
+     /// Updates specific field / column that matches the query filter. Returns a Result of the number of rows affected or error if update was unsuccessful.
+     pub async fn partial_update<T: Default + Struct + Clone>(self, field_name: String, field_value: String) -> Result<usize, AioError> {
+          let db = self.db;
+          let query = QueryBuilder { table_name: self.table_name, query_options: self.query_options, db: &db, transaction: None };
+          return query.partial_update::<T>(field_name, field_value).await;
+     }
+
+     /// Sets an `Option` field / column to NULL (None) for the rows that match the query filter. Returns the number of rows affected.
+     pub async fn partial_update_null<T: Default + Struct + Clone>(self, field_name: String) -> Result<usize, AioError> {
+          let db = self.db;
+          let query = QueryBuilder { table_name: self.table_name, query_options: self.query_options, db: &db, transaction: None };
+          return query.partial_update_null::<T>(field_name).await;
+     }
+
+     /// Deletes **all values** that match the query filter. Returns a Result of the number of rows affected or error if update was unsuccessful.
+     pub async fn delete_value<T: Default + Struct + Clone>(self) -> Result<usize, AioError> {
+          let db = self.db;
+          let query = QueryBuilder { table_name: self.table_name, query_options: self.query_options, db: &db, transaction: None };
+          return query.delete_value::<T>().await;
+     }
+
+     /// Returns if any value / row matches the the query filter.
+     pub async fn any<T: Default + Struct + Clone>(self) -> Result<bool, AioError> {
+          let db = self.db;
+          let query = QueryBuilder { table_name: self.table_name, query_options: self.query_options, db: &db, transaction: None };
+          return query.any::<T>().await;
+     }
+
+     /// Returns the count of values / rows that match the the query filter.
+     pub async fn count<T: Default + Struct + Clone>(self) -> Result<u64, AioError> {
+          let db = self.db;
+          let query = QueryBuilder { table_name: self.table_name, query_options: self.query_options, db: &db, transaction: None };
+          return query.count::<T>().await;
+     }
+
+     /// Returns if all rows / records match the the query filter.
+     pub async fn all<T: Default + Struct + Clone>(self) -> Result<bool, AioError> {
+          let db = self.db;
+          let query = QueryBuilder { table_name: self.table_name, query_options: self.query_options, db: &db, transaction: None };
+          return query.all::<T>().await;
+     }
+}
+
+impl OwnedQueryOption {
+     /// Define the Operator and it's value which will be used for the **WHERE** clause, and the Next which will be used for chaining with the next clause. By default None is equal to **Next::And**.
+     pub fn where_is(self, operator: Operator, next: Option<Next>) -> OwnedQueryBuilder {
+          let mut query = self.query_builder;
+
+          query.query_options.push(QueryOption {
+               field_name: self.field_name,
+               operator: Some(operator),
+               query_builder: None,
+               next: Some(next.unwrap_or(Next::And))
+          });
+
+          return query;
+     }
+}
+
+pub(crate) struct QueryRowResult<T> {
+     pub value: Option<T>,
+}
+
+impl<T: Default + Struct + Clone> QueryRowResult<T> {
+     pub(crate) async fn new(
+          query: BoundQuery, 
+          model_info: Arc<ModelInfo>,
+          connection: &ReadConnection<'_>) -> Result<QueryRowResult<T>, AioError> { 
+          
+          let statement_cache = connection.statement_cache().clone();
+          let value = connection.sqlite_connection().call(move |conn| {
+               let mut stmt = statement_cache.prepare(conn, &query.query)?;
+               let mut rows = stmt.query(params_from_iter(query.params))?;
+               
+               if let Some(row) = rows.next()? {
+                    Ok(map_row_to_struct::<T>(row, &model_info).map(Some))
+               } else {
+                    Ok(Ok(None))
+               }
+          }).await??;
+          
+          return Ok(QueryRowResult { value });
+     }
+}
+
+pub(crate) struct QueryRowsResult<T> {
+     pub value: Vec<T>,
+}
+
+impl<T: Default + Struct + Clone> QueryRowsResult<T> {
+     pub(crate) async fn new_many(
+          query: BoundQuery, 
+          model_info: Arc<ModelInfo>,
+          connection: &ReadConnection<'_>) -> Result<QueryRowsResult<T>, AioError> {
+          
+          let statement_cache = connection.statement_cache().clone();
+          let value = connection.sqlite_connection().call(move |conn| {
+               let mut stmt = statement_cache.prepare(conn, &query.query)?;
+               let mut rows = stmt.query(params_from_iter(query.params))?;
+               
+               let mut results = Vec::new();
+               
+               while let Some(row) = rows.next()? {
+                    match map_row_to_struct::<T>(row, &model_info) {
+                         Ok(mapped_value) => results.push(mapped_value),
+                         Err(error) => return Ok(Err(error))
+                    }
+               }
+               
+               Ok(Ok(results))
+          }).await??;
+          
+          return Ok(QueryRowsResult { value });
+     }
+}
+
+#[derive(Default, Reflect, Clone)]
+pub(crate) struct AnyCountResult {
+     pub count_total: u64
+}
+
+use bevy_reflect::GetField;
+
+// Helper function to map rusqlite::Row to Bevy Struct using reflection
+pub(crate) fn map_row_to_struct<T: Default + Struct + Clone>(row: &Row, model_info: &ModelInfo) -> Result<T, AioError> {
+     let mut instance = T::default();
+
+     let mut struct_mut2: Box<dyn Struct> = Box::new(T::default());
+     let ReflectMut::Struct(reflected2) = struct_mut2.reflect_mut() else { unreachable!() };
+     let struct_immutable: Box<dyn Struct> = Box::new(T::default());
+
+     let mut column_index = 0;
+
+     for (index, field) in struct_immutable.iter_fields().enumerate() {
+          let (field_type, nullable) = get_field_type(field);
+          let field_name = reflected2.name_at(index).unwrap();
+
+          // Skipped fields keep their default value
+          let Some(column_name) = model_info.column_name(field_name) else { continue };
+          let index = column_index;
+          column_index += 1;
+
+          // Try to get the value from the row by field name first, then by index. NULL is None for Option fields and keeps the default value otherwise.
+          match field_type.as_str() {
+               "bool" => {
+                    let value = get_column::<i64>(row, column_name, index)?.map(|x| x != 0);
+                    set_field::<T, bool>(&mut instance, field_name, nullable, value)?;
+               },
+               "u8" => {
+                    let value = get_integer::<u8>(row, column_name, index)?;
+                    set_field::<T, u8>(&mut instance, field_name, nullable, value)?;
+               },
+               "u16" => {
+                    let value = get_integer::<u16>(row, column_name, index)?;
+                    set_field::<T, u16>(&mut instance, field_name, nullable, value)?;
+               },
+               "u32" => {
+                    let value = get_integer::<u32>(row, column_name, index)?;
+                    set_field::<T, u32>(&mut instance, field_name, nullable, value)?;
+               },
+               "u64" => {
+                    // u64 is stored as the i64 with the same bits
+                    let value = get_column::<i64>(row, column_name, index)?.map(|x| x as u64);
+                    set_field::<T, u64>(&mut instance, field_name, nullable, value)?;
+               },
+               "i8" => {
+                    let value = get_integer::<i8>(row, column_name, index)?;
+                    set_field::<T, i8>(&mut instance, field_name, nullable, value)?;
+               },
+               "i16" => {
+                    let value = get_integer::<i16>(row, column_name, index)?;
+                    set_field::<T, i16>(&mut instance, field_name, nullable, value)?;
+               },
+               "i32" => {
+                    let value = get_integer::<i32>(row, column_name, index)?;
+                    set_field::<T, i32>(&mut instance, field_name, nullable, value)?;
+               },
+               "i64" => {
+                    let value = get_column::<i64>(row, column_name, index)?;
+                    set_field::<T, i64>(&mut instance, field_name, nullable, value)?;
+               },
+               "f32" => {
+                    let value = get_column::<f64>(row, column_name, index)?.map(|x| x as f32);
+                    set_field::<T, f32>(&mut instance, field_name, nullable, value)?;
+               },
+               "f64" => {
+                    let value = get_column::<f64>(row, column_name, index)?;
+                    set_field::<T, f64>(&mut instance, field_name, nullable, value)?;
+               },
+               "char" => {
+                    let value = get_column::<String>(row, column_name, index)?.map(|x| x.chars().next().unwrap_or(' '));
+                    set_field::<T, char>(&mut instance, field_name, nullable, value)?;
+               },
+               "String" => {
+                    let value = get_column::<String>(row, column_name, index)?;
+                    set_field::<T, String>(&mut instance, field_name, nullable, value)?;
+               },
+               "Vec" => {
+                    let is_vec_u8 = if nullable {
+                         instance.get_field::<Option<Vec<u8>>>(field_name).is_some()
+                    } else {
+                         instance.get_field::<Vec<u8>>(field_name).is_some()
+                    };
+
+                    if !is_vec_u8 {
+                         return Err(AioError::UnsupportedFieldType(field.reflect_type_path().to_string()));
+                    }
+
+                    // Handle Vec<u8> stored as BLOB or hex string
+                    let value = match get_column::<Value>(row, column_name, index)? {
+                         Some(Value::Blob(bytes)) => Some(bytes),
+                         Some(Value::Text(text)) => Some(hex::decode(&text)
+                              .map_err(|_| AioError::SchemaMismatch(format!("Column {} holds text that isn't hex encoded bytes.", column_name)))?),
+                         Some(other) => return Err(AioError::SchemaMismatch(format!("Column {} holds {:?} instead of bytes.", column_name, other))),
+                         None => None
+                    };
+                    set_field::<T, Vec<u8>>(&mut instance, field_name, nullable, value)?;
+               },
+               _ => {
+                    return Err(AioError::UnsupportedFieldType(field_type));
+               }
+          }
+     }
+
+    Ok(instance)
+}
+
+/// Reads a column by name, falling back to its index. Returns None for NULL, and `AioError::SchemaMismatch` for a value of another type.
+fn get_column<V: FromSql>(row: &Row, column_name: &str, index: usize) -> Result<Option<V>, AioError> {
+     let value = match row.get::<_, Option<V>>(column_name) {
+          Err(rusqlite::Error::InvalidColumnName(_)) => row.get::<_, Option<V>>(index),
+          value => value
+     };
+
+     return value.map_err(|error| AioError::SchemaMismatch(format!("Column {} doesn't match the field: {}.", column_name, error)));
+}
+
+/// Reads an integer column into a smaller integer field, a value out of its range is an `AioError::SchemaMismatch`.
+fn get_integer<V: TryFrom<i64>>(row: &Row, column_name: &str, index: usize) -> Result<Option<V>, AioError> {
+     return get_column::<i64>(row, column_name, index)?
+          .map(|x| V::try_from(x).map_err(|_| AioError::SchemaMismatch(format!("{} of column {} is out of the range of the field.", x, column_name))))
+          .transpose();
+}
+
+/// Sets a field of type **V**, or `Option<V>` if the field is nullable. A None value leaves non nullable fields untouched.
+fn set_field<T: Struct, V: Reflect>(instance: &mut T, field_name: &str, nullable: bool, value: Option<V>) -> Result<(), AioError> 
+where Option<V>: Reflect {
+     let mismatch = || AioError::SchemaMismatch(format!("Couldn't set field {} of the struct of type T.", field_name));
+
+     if nullable {
+          *instance.get_field_mut::<Option<V>>(field_name).ok_or_else(mismatch)? = value;
+     } else if let Some(value) = value {
+          *instance.get_field_mut::<V>(field_name).ok_or_else(mismatch)? = value;
+     }
+
+     return Ok(());
 }
\ No newline at end of file
@@ -0,0 +1,278 @@
+use std::marker::PhantomData;
+
+use bevy_reflect::Struct;
+use rusqlite::ToSql;
+
+use super::aio_database::AioDatabase;
+use super::aio_error::AioError;
+use super::aio_migration::Migrator;
+use super::aio_query::{Next, Operator, QueryBuilder, QueryOption};
+use super::models::{AioModel, BulkInsertMode, InsertManyResult, MigrationReport, Schema, TableDescription, UpsertAction, UpsertResult};
+
+/// A handle to the table of the model **T**. Its calls take and return **T** without a turbofish,
+/// and using it with another model doesn't compile. Clones share the connection.
+/// ### Example
+/// ```rust
+/// let people = AioTable::<Person>::create("G:\\".into(), "Test".into()).await?;
+///
+/// people.insert_value(&person).await?;
+///
+/// let married = people
+///    .query()
+///    .field("married")
+///    .where_is(Operator::Eq(true.to_string()), None)
+///    .get_many_values().await?;
+/// ```
+pub struct AioTable<T> {
+     db: AioDatabase,
+     model: PhantomData<fn() -> T>
+}
+
+impl<T> Clone for AioTable<T> {
+     fn clone(&self) -> AioTable<T> {
+          return AioTable {
+               db: self.db.clone(),
+               model: PhantomData
+          };
+     }
+}
+
+impl<T: Default + Struct + Clone + Send + AioModel> AioTable<T> {
+     /// Create a locally persisted database holding the table of **T**. See `AioDatabase::create`.
+     pub async fn create(location: String, name: String) -> Result<AioTable<T>, AioError> {
+          let db = AioDatabase::create::<T>(location, name).await?;
+          return Ok(AioTable::new(db));
+     }
+
+     /// Create a locally persisted database holding the table of **T**, migrating an existing table with `migrator`.
+     pub async fn create_with_migrator(location: String, name: String, migrator: Migrator) -> Result<(AioTable<T>, MigrationReport), AioError> {
+          let (db, report) = AioDatabase::create_with_migrator::<T>(location, name, migrator).await?;
+          return Ok((AioTable::new(db), report));
+     }
+
+     /// Create an in-memory database holding the table of **T**.
+     pub async fn create_in_memory(name: String) -> Result<AioTable<T>, AioError> {
+          let db = AioDatabase::create_in_memory::<T>(name).await?;
+          return Ok(AioTable::new(db));
+     }
+
+     /// Create an in-memory database holding the table of **T**, created according to `migrator`.
+     pub async fn create_in_memory_with_migrator(name: String, migrator: Migrator) -> Result<(AioTable<T>, MigrationReport), AioError> {
+          let (db, report) = AioDatabase::create_in_memory_with_migrator::<T>(name, migrator).await?;
+          return Ok((AioTable::new(db), report));
+     }
+
+     /// Wraps a handle whose table is the one of **T**.
+     pub(crate) fn new(db: AioDatabase) -> AioTable<T> {
+          return AioTable {
+               db: db,
+               model: PhantomData
+          };
+     }
+
+     /// The untyped database, e.g. for transactions or the settings of the connection.
+     pub fn db(&self) -> &AioDatabase {
+          return &self.db;
+     }
+
+     /// Get the name of the table.
+     pub fn get_name(&self) -> &str {
+          return self.db.get_name();
+     }
+
+     /// Get the schema of the model.
+     pub fn get_schema(&self) -> &Vec<Schema> {
+          return self.db.get_schema();
+     }
+
+     /// Reads the live schema of the table from SQLite. See `AioDatabase::describe`.
+     pub async fn describe(&self) -> Result<TableDescription, AioError> {
+
+          return self.db.describe().await;
+     }
+
+     /// Inserts a value and returns its rowid.
+     pub async fn insert_value(&self, value: &T) -> Result<i64, AioError> {
+
+          return self.db.insert_value(value).await;
+     }
+
+     /// Inserts all values in a single transaction. See `AioDatabase::insert_many`.
+     pub async fn insert_many(&self, values: &[T]) -> Result<InsertManyResult, AioError> {
+
+          return self.db.insert_many(values).await;
+     }
+
+     /// Like `insert_many`, with the given `BulkInsertMode`.
+     pub async fn insert_many_with(&self, values: &[T], mode: BulkInsertMode) -> Result<InsertManyResult, AioError> {
+
+          return self.db.insert_many_with(values, mode).await;
+     }
+
+     /// Inserts all values of the iterator in a single transaction.
+     pub async fn insert_iter(&self, values: impl IntoIterator<Item = T>) -> Result<InsertManyResult, AioError> {
+
+          return self.db.insert_iter(values).await;
+     }
+
+     /// Like `insert_iter`, with the given `BulkInsertMode`.
+     pub async fn insert_iter_with(&self, values: impl IntoIterator<Item = T>, mode: BulkInsertMode) -> Result<InsertManyResult, AioError> {
+
+          return self.db.insert_iter_with(values, mode).await;
+     }
+
+     /// Inserts a value or updates the row with the same `conflict_fields`. See `AioDatabase::upsert`.
+     pub async fn upsert(&self, value: &T, conflict_fields: Vec<String>) -> Result<UpsertResult, AioError> {
+
+          return self.db.upsert(value, conflict_fields).await;
+     }
+
+     /// Like `upsert`, resolving the conflict with `action`.
+     pub async fn upsert_with(&self, value: &T, conflict_fields: Vec<String>, action: UpsertAction) -> Result<UpsertResult, AioError> {
+
+          return self.db.upsert_with(value, conflict_fields, action).await;
+     }
+
+     /// Returns the row whose primary key (or rowid) equals `id`, or **AioError::NotFound**.
+     pub async fn get_by_id(&self, id: impl ToSql) -> Result<T, AioError> {
+
+          return self.db.get_by_id::<T>(id).await;
+     }
+
+     /// Updates every field except the primary key of the row whose key (or rowid) equals `id`. Returns the number of rows affected.
+     pub async fn update_by_id(&self, id: impl ToSql, value: &T) -> Result<usize, AioError> {
+
+          return self.db.update_by_id(id, value).await;
+     }
+
+     /// Deletes the row whose primary key (or rowid) equals `id`. Returns the number of rows affected.
+     pub async fn delete_by_id(&self, id: impl ToSql) -> Result<usize, AioError> {
+
+          return self.db.delete_by_id::<T>(id).await;
+     }
+
+     /// Creates a `TableQuery`, a QueryBuilder whose calls read and write **T**.
+     pub fn query(&self) -> TableQuery<'_, T> {
+          return TableQuery {
+               query: self.db.query(),
+               model: PhantomData
+          };
+     }
+
+     /// Create a non-unique index for a set of columns / struct fields if doesn't exist.
+     pub async fn create_index(&self, index_name: &str, columns: Vec<String>) -> Result<(), AioError> {
+
+          return self.db.create_index::<T>(index_name, columns).await;
+     }
+
+     /// Create a unique index for a set of columns / struct fields if doesn't exist.
+     pub async fn create_unique_index(&self, index_name: &str, columns: Vec<String>) -> Result<(), AioError> {
+
+          return self.db.create_unique_index::<T>(index_name, columns).await;
+     }
+
+     /// Drop an index if exists.
+     pub async fn drop_index(&self, index_name: &str) -> Result<(), AioError> {
+
+          return self.db.drop_index(index_name).await;
+     }
+}
+
+/// The QueryBuilder of an `AioTable`, its calls read and write **T**.
+/// ### Example
+/// ```rust
+/// let deleted = people
+///    .query()
+///    .field("name")
+///    .where_is(Operator::Eq("Mylo".into()), None)
+///    .delete_value().await?;
+/// ```
+pub struct TableQuery<'a, T> {
+     query: QueryBuilder<'a>,
+     model: PhantomData<fn() -> T>
+}
+
+/// Part of TableQuery's API for generating query, see `QueryOption`.
+pub struct TableQueryOption<'a, T> {
+     option: QueryOption<'a>,
+     model: PhantomData<fn() -> T>
+}
+
+impl<'a, T: Default + Struct + Clone> TableQuery<'a, T> {
+     /// Declare which **field (column)** you want to query.
+     pub fn field<'b>(&'b self, name: &str) -> TableQueryOption<'b, T> {
+          return TableQueryOption {
+               option: self.query.field(name),
+               model: PhantomData
+          };
+     }
+
+     /// Clears out all query options
+     pub fn clear(&mut self) {
+          self.query.clear();
+     }
+
+     /// Return the first **value (row)** that matched or **AioError::NotFound** if there are not query matches.
+     pub async fn get_single_value(self) -> Result<T, AioError> {
+
+          return self.query.get_single_value::<T>().await;
+     }
+
+     /// Return the all **values (rows)** that matched or an empty Vec if there are not query matches.
+     pub async fn get_many_values(self) -> Result<Vec<T>, AioError> {
+
+          return self.query.get_many_values::<T>().await;
+     }
+
+     /// Updates **all values** that matches the query filter with `value`. Returns the number of rows affected.
+     pub async fn update_value(self, value: T) -> Result<usize, AioError> {
+
+          return self.query.update_value::<T>(value).await;
+     }
+
+     /// Updates specific field / column that matches the query filter. Returns the number of rows affected.
+     pub async fn partial_update(self, field_name: String, field_value: String) -> Result<usize, AioError> {
+
+          return self.query.partial_update::<T>(field_name, field_value).await;
+     }
+
+     /// Sets an `Option` field / column to NULL (None) for the rows that match the query filter. Returns the number of rows affected.
+     pub async fn partial_update_null(self, field_name: String) -> Result<usize, AioError> {
+
+          return self.query.partial_update_null::<T>(field_name).await;
+     }
+
+     /// Deletes **all values** that match the query filter. Returns the number of rows affected.
+     pub async fn delete_value(self) -> Result<usize, AioError> {
+
+          return self.query.delete_value::<T>().await;
+     }
+
+     /// Returns if any value / row matches the the query filter.
+     pub async fn any(self) -> Result<bool, AioError> {
+
+          return self.query.any::<T>().await;
+     }
+
+     /// Returns the count of values / rows that match the the query filter.
+     pub async fn count(self) -> Result<u64, AioError> {
+
+          return self.query.count::<T>().await;
+     }
+
+     /// Returns if all rows / records match the the query filter.
+     pub async fn all(self) -> Result<bool, AioError> {
+
+          return self.query.all::<T>().await;
+     }
+}
+
+impl<T> TableQueryOption<'_, T> {
+     /// Define the Operator and it's value used for the **WHERE** clause, see `QueryOption::where_is`.
+     pub fn where_is<'b>(&'b self, operator: Operator, next: Option<Next>) -> TableQuery<'b, T> {
+          return TableQuery {
+               query: self.option.where_is(operator, next),
+               model: PhantomData
+          };
+     }
+}
@@ -0,0 +1,469 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy_reflect::Struct;
+use log::{error, trace};
+use rusqlite::{params_from_iter, ToSql};
+use rusqlite::types::Value;
+use tokio::sync::OwnedMutexGuard;
+use tokio_rusqlite::Connection as SqliteConnection;
+
+use super::aio_database::{AioDatabase, ConnectionState};
+use super::aio_error::AioError;
+use super::aio_query::QueryBuilder;
+use super::internal::helpers::to_sql_value;
+use super::models::{BulkInsertMode, InsertManyResult, UpsertAction, UpsertResult};
+
+/// How SQLite acquires its locks when a transaction begins.
+/// - **Deferred** = no lock until the first read / write (SQLite's default)
+/// - **Immediate** = take the write lock right away, other writers get `Busy`
+/// - **Exclusive** = like Immediate, and in rollback journal mode also blocks readers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransactionMode {
+     #[default]
+     Deferred,
+     Immediate,
+     Exclusive
+}
+
+impl fmt::Display for TransactionMode {
+     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+          match self {
+               TransactionMode::Deferred => write!(f, "DEFERRED"),
+               TransactionMode::Immediate => write!(f, "IMMEDIATE"),
+               TransactionMode::Exclusive => write!(f, "EXCLUSIVE")
+          }
+     }
+}
+
+/// An open transaction, created by `AioDatabase::begin` or `AioDatabase::transaction`, or a savepoint nested in it, created by `savepoint`.
+///
+/// Exposes the same insert / query / update / delete API as `AioDatabase`. Until it's committed or rolled back the connection is reserved for it,
+/// so calls made through the `AioDatabase` itself wait for the transaction to finish, except reads running on the readers of the pool. If every clone is dropped without `commit` it is rolled back.
+/// ### Example
+/// ```rust
+/// let tx = file_db.begin().await?;
+/// tx.insert_value(&person).await?;
+/// tx.query()
+///    .field("name")
+///    .where_is(Operator::Eq("Mylo".into()), None)
+///    .delete_value::<Person>().await?;
+/// tx.commit().await?;
+/// ```
+#[derive(Clone)]
+pub struct AioTransaction<'a> {
+     pub(crate) db: &'a AioDatabase,
+     state: Arc<TransactionState>,
+     savepoint: Option<Arc<SavepointState>>
+}
+
+struct TransactionState {
+     sqlite_connection: SqliteConnection,
+     guard: Mutex<Option<OwnedMutexGuard<ConnectionState>>>,
+     /// Ids and names of the open savepoints, the innermost last.
+     savepoints: Mutex<Vec<(u64, String)>>,
+     next_savepoint_id: AtomicU64,
+     /// `ROLLBACK TO` / `RELEASE` statements of dropped savepoints, executed before the next statement of the transaction.
+     pending_cleanups: Mutex<Vec<String>>
+}
+
+struct SavepointState {
+     id: u64,
+     name: String,
+     transaction: Arc<TransactionState>,
+     // Keeps the enclosing savepoint open at least as long as this one.
+     _parent: Option<Arc<SavepointState>>
+}
+
+impl<'a> AioTransaction<'a> {
+     pub(crate) async fn begin(db: &'a AioDatabase, mode: TransactionMode) -> Result<AioTransaction<'a>, AioError> {
+          let guard = db.conn.lock().await?;
+
+          return AioTransaction::begin_locked(db, mode, guard).await;
+     }
+
+     /// Same as `begin`, on the connection the caller already reserved with `guard`.
+     pub(crate) async fn begin_locked(db: &'a AioDatabase, mode: TransactionMode, guard: OwnedMutexGuard<ConnectionState>) -> Result<AioTransaction<'a>, AioError> {
+          let query = format!("BEGIN {} TRANSACTION", mode);
+          trace!("Executing begin query: {}", query);
+
+          db.conn.sqlite_connection.call(move |conn| {
+               conn.execute_batch(&query)?;
+               Ok(())
+          }).await?;
+
+          return Ok(AioTransaction {
+               db: db,
+               state: Arc::new(TransactionState {
+                    sqlite_connection: db.conn.sqlite_connection.clone(),
+                    guard: Mutex::new(Some(guard)),
+                    savepoints: Mutex::new(Vec::new()),
+                    next_savepoint_id: AtomicU64::new(0),
+                    pending_cleanups: Mutex::new(Vec::new())
+               }),
+               savepoint: None
+          });
+     }
+
+     /// Creates a savepoint nested in this transaction / savepoint. Committing it releases the savepoint, rolling it back (or dropping it) undoes only the changes made through it.
+     /// ### Example
+     /// ```rust
+     /// let batch = tx.savepoint("batch_1").await?;
+     /// batch.insert_value(&person).await?;
+     /// if !valid {
+     ///     batch.rollback().await?;
+     /// } else {
+     ///     batch.commit().await?;
+     /// }
+     /// ```
+     pub async fn savepoint(&self, name: &str) -> Result<AioTransaction<'a>, AioError> {
+          self.ensure_active().await?;
+
+          if self.state.savepoints.lock().unwrap().iter().any(|(_, open_name)| open_name == name) {
+               return Err(AioError::Other(format!("Savepoint {} is already open.", name)));
+          }
+
+          let query = format!("SAVEPOINT {}", quote_identifier(name));
+          trace!("Executing savepoint query: {}", query);
+
+          self.state.sqlite_connection.call(move |conn| {
+               conn.execute_batch(&query)?;
+               Ok(())
+          }).await?;
+
+          let id = self.state.next_savepoint_id.fetch_add(1, Ordering::Relaxed);
+          self.state.savepoints.lock().unwrap().push((id, name.to_string()));
+
+          return Ok(AioTransaction {
+               db: self.db,
+               state: self.state.clone(),
+               savepoint: Some(Arc::new(SavepointState {
+                    id: id,
+                    name: name.to_string(),
+                    transaction: self.state.clone(),
+                    _parent: self.savepoint.clone()
+               }))
+          });
+     }
+
+     /// Commits the transaction, or releases the savepoint. If the commit fails the transaction is rolled back and the error returned.
+     pub async fn commit(self) -> Result<(), AioError> {
+          if let Some(savepoint) = &self.savepoint {
+               self.ensure_active().await?;
+
+               let query = format!("RELEASE {}", quote_identifier(&savepoint.name));
+               self.state.close_savepoint(savepoint.id);
+
+               self.state.sqlite_connection.call(move |conn| {
+                    conn.execute_batch(&query)?;
+                    Ok(())
+               }).await?;
+
+               return Ok(());
+          }
+
+          return self.commit_locked().await.map(drop);
+     }
+
+     /// Commits the transaction like `commit`, but hands the connection back to the caller instead of releasing it.
+     pub(crate) async fn commit_locked(self) -> Result<OwnedMutexGuard<ConnectionState>, AioError> {
+          self.state.flush_cleanups().await?;
+          let guard = self.take_guard()?;
+
+          self.state.sqlite_connection.call(|conn| {
+               if let Err(error) = conn.execute_batch("COMMIT") {
+                    if !conn.is_autocommit() {
+                         _ = conn.execute_batch("ROLLBACK");
+                    }
+                    return Err(error.into());
+               }
+               Ok(())
+          }).await?;
+
+          return Ok(guard);
+     }
+
+     /// Rolls back every change made in the transaction, or since the savepoint was created.
+     pub async fn rollback(self) -> Result<(), AioError> {
+          if let Some(savepoint) = &self.savepoint {
+               self.ensure_active().await?;
+
+               let query = rollback_to_query(&savepoint.name);
+               self.state.close_savepoint(savepoint.id);
+
+               self.state.sqlite_connection.call(move |conn| {
+                    conn.execute_batch(&query)?;
+                    Ok(())
+               }).await?;
+
+               return Ok(());
+          }
+
+          let guard = self.take_guard()?;
+
+          let result = self.state.sqlite_connection.call(|conn| {
+               if !conn.is_autocommit() {
+                    conn.execute_batch("ROLLBACK")?;
+               }
+               Ok(())
+          }).await;
+
+          drop(guard);
+
+          return result.map_err(AioError::from);
+     }
+
+     /// Returns true until the transaction / savepoint is committed or rolled back.
+     pub fn is_active(&self) -> bool {
+          if self.state.guard.lock().unwrap().is_none() {
+               return false;
+          }
+
+          return match &self.savepoint {
+               Some(savepoint) => self.state.is_savepoint_open(savepoint.id),
+               None => true
+          };
+     }
+
+     /// Inserts a **T** value as part of the transaction and returns its rowid.
+     pub async fn insert_value<T: Default + Struct + Clone + Send>(&self, value: &T) -> Result<i64, AioError> {
+
+          return self.db.insert_value_in(value, Some(self)).await;
+     }
+
+     /// Inserts a **T** value or updates the row with the same `conflict_fields` as part of the transaction. See `AioDatabase::upsert`.
+     pub async fn upsert<T: Default + Struct + Clone + Send>(&self, value: &T, conflict_fields: Vec<String>) -> Result<UpsertResult, AioError> {
+
+          return self.db.upsert_in(value, conflict_fields, UpsertAction::UpdateAll, Some(self)).await;
+     }
+
+     /// Like `upsert`, resolving the conflict with `action`.
+     pub async fn upsert_with<T: Default + Struct + Clone + Send>(&self, value: &T, conflict_fields: Vec<String>, action: UpsertAction) -> Result<UpsertResult, AioError> {
+
+          return self.db.upsert_in(value, conflict_fields, action, Some(self)).await;
+     }
+
+     /// Returns the row whose primary key (or rowid) equals `id`, or **AioError::NotFound**.
+     pub async fn get_by_id<T: Default + Struct + Clone + Send>(&self, id: impl ToSql) -> Result<T, AioError> {
+
+          return self.db.get_by_id_in(to_sql_value(&id)?, Some(self)).await;
+     }
+
+     /// Updates every field except the primary key of the row whose key (or rowid) equals `id`. Returns the number of rows affected.
+     pub async fn update_by_id<T: Default + Struct + Clone + Send>(&self, id: impl ToSql, value: &T) -> Result<usize, AioError> {
+
+          return self.db.update_by_id_in(to_sql_value(&id)?, value, Some(self)).await;
+     }
+
+     /// Deletes the row whose primary key (or rowid) equals `id`. Returns the number of rows affected.
+     pub async fn delete_by_id<T: Default + Struct + Clone + Send>(&self, id: impl ToSql) -> Result<usize, AioError> {
+
+          return self.db.delete_by_id_in::<T>(to_sql_value(&id)?, Some(self)).await;
+     }
+
+     /// Inserts all values as part of the transaction. If a row fails none of them is inserted and its error is returned.
+     pub async fn insert_many<T: Default + Struct + Clone + Send>(&self, values: &[T]) -> Result<InsertManyResult, AioError> {
+
+          return self.db.insert_many_in::<T, _>(values.iter(), Some(self), BulkInsertMode::AllOrNothing).await;
+     }
+
+     /// Like `insert_many`, with `BulkInsertMode::SkipFailed` the failed rows are skipped and reported with their index instead.
+     pub async fn insert_many_with<T: Default + Struct + Clone + Send>(&self, values: &[T], mode: BulkInsertMode) -> Result<InsertManyResult, AioError> {
+
+          return self.db.insert_many_in::<T, _>(values.iter(), Some(self), mode).await;
+     }
+
+     /// Inserts all values of the iterator as part of the transaction. If a row fails none of them is inserted and its error is returned.
+     pub async fn insert_iter<T: Default + Struct + Clone + Send>(&self, values: impl IntoIterator<Item = T>) -> Result<InsertManyResult, AioError> {
+
+          return self.db.insert_many_in::<T, _>(values, Some(self), BulkInsertMode::AllOrNothing).await;
+     }
+
+     /// Like `insert_iter`, with `BulkInsertMode::SkipFailed` the failed rows are skipped and reported with their index instead.
+     pub async fn insert_iter_with<T: Default + Struct + Clone + Send>(&self, values: impl IntoIterator<Item = T>, mode: BulkInsertMode) -> Result<InsertManyResult, AioError> {
+
+          return self.db.insert_many_in::<T, _>(values, Some(self), mode).await;
+     }
+
+     /// Executes one or more SQL statements separated by `;` as part of the transaction, e.g. a data fix in a `MigrationStep`.
+     pub async fn execute_batch(&self, sql: &str) -> Result<(), AioError> {
+          self.ensure_active().await?;
+
+          let sql = sql.to_string();
+          trace!("Executing batch query: {}", sql);
+
+          self.state.sqlite_connection.call(move |conn| {
+               conn.execute_batch(&sql)?;
+               Ok(())
+          }).await?;
+
+          return Ok(());
+     }
+
+     pub(crate) async fn execute(&self, sql: &str, params: Vec<Value>) -> Result<usize, AioError> {
+          self.ensure_active().await?;
+
+          let sql = sql.to_string();
+          trace!("Executing query: {}", sql);
+
+          let rows = self.state.sqlite_connection.call(move |conn| {
+               Ok(conn.execute(&sql, params_from_iter(params))?)
+          }).await?;
+
+          return Ok(rows);
+     }
+
+     /// Creates a QueryBuilder whose get / update / delete / count calls run as part of the transaction.
+     pub fn query(&self) -> QueryBuilder<'_> {
+          return QueryBuilder {
+               table_name: self.db.get_name().to_string(),
+               query_options: Vec::default(),
+               db: self.db,
+               transaction: Some(self)
+          };
+     }
+
+     /// Checks that the transaction / savepoint is still open and rolls back savepoints that were dropped in the meantime.
+     pub(crate) async fn ensure_active(&self) -> Result<(), AioError> {
+          if !self.is_active() {
+               return Err(AioError::TransactionFinished);
+          }
+
+          return self.state.flush_cleanups().await;
+     }
+
+     fn take_guard(&self) -> Result<OwnedMutexGuard<ConnectionState>, AioError> {
+          return self.state.guard.lock().unwrap().take().ok_or(AioError::TransactionFinished);
+     }
+}
+
+impl TransactionState {
+     fn is_savepoint_open(&self, id: u64) -> bool {
+          return self.savepoints.lock().unwrap().iter().any(|(open_id, _)| *open_id == id);
+     }
+
+     /// Removes the savepoint and the ones nested in it from the open savepoints. Returns false if it was already closed.
+     fn close_savepoint(&self, id: u64) -> bool {
+          let mut savepoints = self.savepoints.lock().unwrap();
+
+          return match savepoints.iter().position(|(open_id, _)| *open_id == id) {
+               Some(position) => {
+                    savepoints.truncate(position);
+                    true
+               },
+               None => false
+          };
+     }
+
+     async fn flush_cleanups(&self) -> Result<(), AioError> {
+          let cleanups: Vec<String> = self.pending_cleanups.lock().unwrap().drain(..).collect();
+
+          if cleanups.is_empty() {
+               return Ok(());
+          }
+
+          self.sqlite_connection.call(move |conn| {
+               for cleanup in cleanups.iter() {
+                    trace!("Executing dropped savepoint query: {}", cleanup);
+                    conn.execute_batch(cleanup)?;
+               }
+               Ok(())
+          }).await?;
+
+          return Ok(());
+     }
+}
+
+/// A savepoint opened by an internal call over several calls to the connection thread. If the call is dropped or fails before
+/// closing it, it's rolled back before the next statement of the transaction, or with the implicit transaction before the next statement on the connection.
+pub(crate) struct OpenSavepoint<'g> {
+     name: &'static str,
+     connection: Option<&'g mut ConnectionState>,
+     transaction: Option<Arc<TransactionState>>,
+     closed: bool
+}
+
+impl<'g> OpenSavepoint<'g> {
+     pub(crate) fn new(name: &'static str, connection: Option<&'g mut ConnectionState>, transaction: Option<&AioTransaction<'_>>) -> OpenSavepoint<'g> {
+          return OpenSavepoint {
+               name: name,
+               connection: connection,
+               transaction: transaction.map(|x| x.state.clone()),
+               closed: false
+          };
+     }
+
+     pub(crate) fn name(&self) -> &'static str {
+          return self.name;
+     }
+
+     /// Marks the savepoint as released or rolled back.
+     pub(crate) fn close(&mut self) {
+          self.closed = true;
+     }
+}
+
+impl Drop for OpenSavepoint<'_> {
+     fn drop(&mut self) {
+          if self.closed {
+               return;
+          }
+
+          if let Some(connection) = self.connection.as_mut() {
+               connection.pending_rollback = true;
+          }
+
+          if let Some(transaction) = &self.transaction {
+               transaction.pending_cleanups.lock().unwrap().push(rollback_to_query(self.name));
+          }
+     }
+}
+
+impl Drop for SavepointState {
+     fn drop(&mut self) {
+          if self.transaction.close_savepoint(self.id) {
+               self.transaction.pending_cleanups.lock().unwrap().push(rollback_to_query(&self.name));
+          }
+     }
+}
+
+fn quote_identifier(name: &str) -> String {
+     return format!("\"{}\"", name.replace('"', "\"\""));
+}
+
+fn rollback_to_query(name: &str) -> String {
+     let name = quote_identifier(name);
+     return format!("ROLLBACK TO {name}; RELEASE {name}");
+}
+
+impl Drop for TransactionState {
+     fn drop(&mut self) {
+          let Some(mut guard) = self.guard.get_mut().unwrap().take() else { return; };
+
+          // Roll back in the background, holding the connection until it's done. Without a runtime the next caller rolls back instead.
+          match tokio::runtime::Handle::try_current() {
+               Ok(handle) => {
+                    let sqlite_connection = self.sqlite_connection.clone();
+                    guard.pending_rollback = true;
+
+                    handle.spawn(async move {
+                         let result = sqlite_connection.call(|conn| {
+                              if !conn.is_autocommit() {
+                                   conn.execute_batch("ROLLBACK")?;
+                              }
+                              Ok(())
+                         }).await;
+
+                         match result {
+                              Ok(_) => guard.pending_rollback = false,
+                              Err(error) => error!("Rolling back a dropped transaction failed. Message: {:?}", error)
+                         }
+                    });
+               },
+               Err(_) => {
+                    guard.pending_rollback = true;
+               }
+          }
+     }
+}
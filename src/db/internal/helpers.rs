@@ -1,434 +1,375 @@
-use bevy_reflect::{GetField, ReflectMut, ReflectRef, Struct};
-use log::debug;
-use crate::db::{aio_query::{Next, Operator, QueryRowResult, QueryRowsResult}, models::{GenericValue, Schema}};
-
-pub(crate) fn get_system_char_delimiter() -> &'static str {
-     let os = std::env::consts::OS;
-     if os == "windows" {
-         "\\"
-     }
-     else if os == "linux" {
-         "/"
-     }
-     else {
-         panic!("OS not supported.");
-     }
-}
-
-pub(crate) fn get_schema_from_generic<T:  Default + Struct>() -> Box<Vec<Schema>> {  
-     let default_t = T::default();
-     let default_t2 = T::default();
-     let my_struct: Box<dyn Struct> = Box::new(default_t);
-
-     let ReflectRef::Struct(reflected) = default_t2.reflect_ref() else { unreachable!() };
-
-     let count = my_struct.iter_fields().count();
-     let mut schema_vec: Box<Vec<Schema>> = Box::new(Vec::with_capacity(count));
-
-     for (i, field) in my_struct.iter_fields().enumerate() {
-          let field_name = reflected.name_at(i).unwrap();
-          let field_type = field.reflect_type_ident().unwrap();
-          debug!("Found field named '{}' of type '{}'", field_name, field_type);
-
-          schema_vec.push(Schema {
-               field_name: field_name.into(),
-               field_type: field_type.into()
-          });
-     }
-     
-     return schema_vec;
-}
-
-pub(crate) fn get_values_from_generic<'a, T:  Default + Struct + Clone>(value: &'a T) -> Vec<GenericValue<'a>> {  
-     let copied_value = value.clone();
-     let my_struct: Box<dyn Struct> = Box::new(copied_value);
-
-     let ReflectRef::Struct(reflected) = value.reflect_ref() else { unreachable!() };
-
-     let count = my_struct.iter_fields().count();
-     let mut schema_vec: Vec<GenericValue> = Vec::with_capacity(count);
-
-     for (i, field) in my_struct.iter_fields().enumerate() {
-          let field_name = reflected.name_at(i).unwrap();
-          let field_value = reflected.field_at(i).unwrap();
-          let field_type = field.reflect_type_ident().unwrap();
-          
-          debug!("Found field named '{}' with value '{:?}'", field_name, field_value);
-    
-          schema_vec.push(GenericValue {
-               field_name: field_name.into(),
-               field_value: field_value,
-               field_type: field_type.into()
-          });
-     }
-     
-     return schema_vec;
-}
-
-pub(crate) fn set_values_from_row_result<'a, T:  Default + Struct + Clone>(row_result: &mut QueryRowResult<T>) -> Result<T, ()> {  
-     let mut struct_mut2: Box<dyn Struct> = Box::new(T::default());
-     let ReflectMut::Struct(reflected2) = struct_mut2.reflect_mut() else { unreachable!() };
-     let struct_immutable: Box<dyn Struct> = Box::new(T::default());
-     
-     let mut t_struct = T::default();
-
-     if let Some(row) = row_result.value.as_ref() {
-          for (i, field) in struct_immutable.iter_fields().enumerate() {
-               let field_type = field.reflect_type_ident().unwrap();
-               let field_name = reflected2.name_at(i).clone().unwrap();
-
-               match field_type {
-                    "bool" => {
-                         *t_struct.get_field_mut::<bool>(field_name).unwrap() = match row.get_field::<bool>(field_name).unwrap_or(&false) {
-                              false => false,
-                              true => true
-                         };
-                    },
-                    "u8" => {
-                         *t_struct.get_field_mut::<u8>(field_name).unwrap() = *row.get_field::<u8>(field_name).unwrap_or(&0) as u8;
-                    },
-                    "u16" => {
-                         *t_struct.get_field_mut::<u16>(field_name).unwrap() = *row.get_field::<u16>(field_name).unwrap_or(&0) as u16;
-                    },
-                    "u32" => {
-                         *t_struct.get_field_mut::<u32>(field_name).unwrap() = *row.get_field::<u32>(field_name).unwrap_or(&0) as u32;
-                    },
-                    "u64" => {
-                         *t_struct.get_field_mut::<u64>(field_name).unwrap() = *row.get_field::<u64>(field_name).unwrap_or(&0) as u64;
-                    },
-                    "i8" => {
-                         *t_struct.get_field_mut::<i8>(field_name).unwrap() = *row.get_field::<i8>(field_name).unwrap_or(&0) as i8;
-                    },
-                    "i16" => {
-                         *t_struct.get_field_mut::<i16>(field_name).unwrap() = *row.get_field::<i16>(field_name).unwrap_or(&0) as i16;
-                    },
-                    "i32" => {
-                         *t_struct.get_field_mut::<i32>(field_name).unwrap() = *row.get_field::<i32>(field_name).unwrap_or(&0) as i32;
-                    },
-                    "i64" => {
-                         *t_struct.get_field_mut::<i64>(field_name).unwrap() = *row.get_field::<i64>(field_name).unwrap_or(&0) as i64;
-                    },
-                    "f32" => {
-                         *t_struct.get_field_mut::<f32>(field_name).unwrap() = *row.get_field::<f32>(field_name).unwrap_or(&0.00f32) as f32;
-                    },
-                    "f64" => {
-                         *t_struct.get_field_mut::<f64>(field_name).unwrap() = *row.get_field::<f64>(field_name).unwrap_or(&0.00f64) as f64;
-                    },
-                    "char" => {
-                         *t_struct.get_field_mut::<char>(field_name).unwrap() = *row.get_field::<char>(field_name).unwrap_or(&' ') as char;
-                    },
-                    "String" => {
-                          let mut buffer: Vec<u8> = Vec::new();
-                         buffer.extend_from_slice(row.get_field::<String>(field_name).unwrap_or(&"".into()).as_bytes());
-                         *t_struct.get_field_mut::<String>(field_name).unwrap() = String::from_utf8_lossy(&buffer).into_owned();
-                    },
-                    "Vec" => {
-                         let mut buffer: Vec<u8> = Vec::new();
-                         buffer.extend_from_slice(row.get_field::<String>(field_name).unwrap_or(&"".into()).as_bytes());
-                         //let vec_u8_data = hex::decode(value).unwrap();
-                         *t_struct.get_field_mut::<Vec<u8>>(field_name).unwrap() = buffer;
-                    },
-                    _ => panic!("{} type not supported.", field_type)
-               }
-          }
-     } else {
-          return Err(());
-     }
-
-     return Ok(t_struct);
-}
-
-pub(crate) fn set_values_from_many_rows_result<'a, T:  Default + Struct + Clone>(row_result: &mut QueryRowsResult<T>) -> Result<Vec<T>, ()> {  
-     let mut struct_mut2: Box<dyn Struct> = Box::new(T::default());
-     let ReflectMut::Struct(reflected2) = struct_mut2.reflect_mut() else { unreachable!() };
-     let struct_immutable: Box<dyn Struct> = Box::new(T::default());
-
-     let mut vec_t: Vec<T> = Vec::default();
-
-     if let Some(rows) = row_result.value.as_ref() {
-          let count = rows.len();
-          let mut i = 0;
-
-          while i < count {
-               if let Some(row) = rows.get(i) {
-                    let row = row.as_ref().unwrap();
-
-                    let mut t_struct = T::default();
-
-                    for (i, field) in struct_immutable.iter_fields().enumerate() {
-                         let field_type = field.reflect_type_ident().unwrap();
-                         let field_name = reflected2.name_at(i).clone().unwrap();
-          
-                         match field_type {
-                              "bool" => {
-                                   *t_struct.get_field_mut::<bool>(field_name).unwrap() = match row.get_field::<bool>(field_name).unwrap_or(&false) {
-                                        false => false,
-                                        true => true
-                                   };
-                              },
-                              "u8" => {
-                                   *t_struct.get_field_mut::<u8>(field_name).unwrap() = *row.get_field::<u8>(field_name).unwrap_or(&0) as u8;
-                              },
-                              "u16" => {
-                                   *t_struct.get_field_mut::<u16>(field_name).unwrap() = *row.get_field::<u16>(field_name).unwrap_or(&0) as u16;
-                              },
-                              "u32" => {
-                                   *t_struct.get_field_mut::<u32>(field_name).unwrap() = *row.get_field::<u32>(field_name).unwrap_or(&0) as u32;
-                              },
-                              "u64" => {
-                                   *t_struct.get_field_mut::<u64>(field_name).unwrap() = *row.get_field::<u64>(field_name).unwrap_or(&0) as u64;
-                              },
-                              "i8" => {
-                                   *t_struct.get_field_mut::<i8>(field_name).unwrap() = *row.get_field::<i8>(field_name).unwrap_or(&0) as i8;
-                              },
-                              "i16" => {
-                                   *t_struct.get_field_mut::<i16>(field_name).unwrap() = *row.get_field::<i16>(field_name).unwrap_or(&0) as i16;
-                              },
-                              "i32" => {
-                                   *t_struct.get_field_mut::<i32>(field_name).unwrap() = *row.get_field::<i32>(field_name).unwrap_or(&0) as i32;
-                              },
-                              "i64" => {
-                                   *t_struct.get_field_mut::<i64>(field_name).unwrap() = *row.get_field::<i64>(field_name).unwrap_or(&0) as i64;
-                              },
-                              "f32" => {
-                                   *t_struct.get_field_mut::<f32>(field_name).unwrap() = *row.get_field::<f32>(field_name).unwrap_or(&0.00f32) as f32;
-                              },
-                              "f64" => {
-                                   *t_struct.get_field_mut::<f64>(field_name).unwrap() = *row.get_field::<f64>(field_name).unwrap_or(&0.00f64) as f64;
-                              },
-                              "char" => {
-                                   *t_struct.get_field_mut::<char>(field_name).unwrap() = *row.get_field::<char>(field_name).unwrap_or(&' ') as char;
-                              },
-                              "String" => {
-                                   let mut buffer: Vec<u8> = Vec::new();
-                                   buffer.extend_from_slice(row.get_field::<String>(field_name).unwrap_or(&"".into()).as_bytes());
-                                   *t_struct.get_field_mut::<String>(field_name).unwrap() = String::from_utf8_lossy(&buffer).into_owned();
-                              },
-                              "Vec" => {
-                                   let mut buffer: Vec<u8> = Vec::new();
-                                   buffer.extend_from_slice(row.get_field::<String>(field_name).unwrap_or(&"".into()).as_bytes());
-                                   //let vec_u8_data = hex::decode(value).unwrap();
-                                   *t_struct.get_field_mut::<Vec<u8>>(field_name).unwrap() = buffer;
-                              },
-                              _ => panic!("{} type not supported.", field_type)
-                         }
-                    }
-          
-                    vec_t.push(t_struct);
-               } else {
-                    break;
-               }
-
-               i += 1;
-          }
-
-          return Ok(vec_t);
-     }
-
-     return Ok(vec![]); // Return an empty vector if no rows are found.
-}
-
-pub(crate) fn get_next(next: &Next) -> String {
-     if next == &Next::And {
-          return "AND".into();
-     }
-     else {
-          return "OR".into();
-     }
-}
-
-pub(crate) fn get_end_value(value: &str, field_type: &str) -> String {
-     let value = value.to_string();
-
-     let end_value: String;
-
-     if field_type == "String" {
-          end_value = format!("'{}'", value);
-     }
-     else {
-         end_value = value;
-     }
-
-     return end_value;
-}
-
-pub(crate) fn push_str_to_query_string(
-     query_string: &mut String, 
-     field_name: &str, 
-     end_value: &str, 
-     last_item: bool, 
-     sql_operator: &str, 
-     next: Option<&Next>) {
-     if !last_item {
-          let next = next.unwrap();
-          let continuation = format!("{} {} {} {} ", field_name, sql_operator, end_value, get_next(next));
-          query_string.push_str(continuation.as_str());
-     } else {
-          let continuation = format!("{} {} {}", field_name, sql_operator, end_value);
-          query_string.push_str(continuation.as_str());
-     }
-}
-
-pub(crate) fn push_contains_to_query_string(
-     query_string: &mut String, 
-     field_name: &str, 
-     end_value: &str, 
-     last_item: bool, 
-     next: Option<&Next>) {
-     let end_value = end_value.to_string();
-
-     let like_end_value = format!("'%{}%'", end_value.replace("'", "''"));
-
-     if !last_item {
-          let next = next.unwrap();
-          let continuation = format!("{} LIKE {} {} ", field_name, like_end_value, get_next(next));
-          query_string.push_str(continuation.as_str());
-     } else {
-          let continuation = format!("{} LIKE {}", field_name, like_end_value);
-          query_string.push_str(continuation.as_str());
-     }
-}
-
-pub(crate) fn push_starts_with_to_query_string(
-     query_string: &mut String, 
-     field_name: &str, 
-     end_value: &str, 
-     last_item: bool, 
-     next: Option<&Next>) {
-     let end_value = end_value.to_string();
-
-     let like_end_value = format!("'{}%'", end_value.replace("'", "''"));
-
-     if !last_item {
-          let next = next.unwrap();
-          let continuation = format!("{} LIKE {} {} ", field_name, like_end_value, get_next(next));
-          query_string.push_str(continuation.as_str());
-     } else {
-          let continuation = format!("{} LIKE {}", field_name, like_end_value);
-          query_string.push_str(continuation.as_str());
-     }
-}
-
-pub(crate) fn push_ends_with_to_query_string(
-     query_string: &mut String, 
-     field_name: &str, 
-     end_value: &str, 
-     last_item: bool, 
-     next: Option<&Next>) {
-     let end_value = end_value.to_string();
-
-     let like_end_value = format!("'%{}'", end_value.replace("'", "''"));
-
-     if !last_item {
-          let next = next.unwrap();
-          let continuation = format!("{} LIKE {} {} ", field_name, like_end_value, get_next(next));
-          query_string.push_str(continuation.as_str());
-     } else {
-          let continuation = format!("{} LIKE {}", field_name, like_end_value);
-          query_string.push_str(continuation.as_str());
-     }
-}
-
-pub(crate) fn query_match_operators(
-     operator: &Operator, 
-     query_string: &mut String, 
-     field_name: &str, 
-     field_type: &str, 
-     last_item: bool, 
-     next: Option<&Next>) {
-     match operator {
-          Operator::Eq(value) => {
-               let end_value = get_end_value(&value, field_type);
-               push_str_to_query_string(
-                    query_string, 
-                    field_name,
-                    &end_value,
-                    last_item, 
-                    "==",
-                    next
-               );
-          },
-          Operator::Ne(value) => {
-               let end_value = get_end_value(&value, field_type);
-               push_str_to_query_string(
-                    query_string, 
-                    field_name,
-                    &end_value,
-                    last_item, 
-                    "<>",
-                    next
-               );
-          },
-          Operator::Gt(value) => {
-               let end_value = get_end_value(&value, field_type);
-               push_str_to_query_string(
-                    query_string, 
-                    field_name,
-                    &end_value,
-                    last_item, 
-                    ">",
-                    next
-               );
-          },
-          Operator::Lt(value) => {
-               let end_value = get_end_value(&value, field_type);
-               push_str_to_query_string(
-                    query_string, 
-                    field_name,
-                    &end_value,
-                    last_item, 
-                    "<",
-                    next
-               );
-          },
-          Operator::Ge(value) => {
-               let end_value = get_end_value(&value, field_type);
-               push_str_to_query_string(
-                    query_string, 
-                    field_name,
-                    &end_value,
-                    last_item, 
-                    ">=",
-                    next
-               );
-          },
-          Operator::Le(value) => {
-               let end_value = get_end_value(&value, field_type);
-               push_str_to_query_string(
-                    query_string, 
-                    field_name,
-                    &end_value,
-                    last_item, 
-                    "<=",
-                    next
-               );
-          },
-          Operator::Contains(value) => {
-               push_contains_to_query_string(
-                    query_string, 
-                    field_name,
-                    &value,
-                    last_item,
-                    next
-               );
-          },
-          Operator::StartsWith(value) => {
-               push_starts_with_to_query_string(
-                    query_string, 
-                    field_name,
-                    &value,
-                    last_item,
-                    next
-               );
-          },
-          Operator::EndsWith(value) => {
-               push_ends_with_to_query_string(
-                    query_string, 
-                    field_name,
-                    &value,
-                    last_item,
-                    next
-               );
-          }
-     }
-}
\ No newline at end of file
+use bevy_reflect::{PartialReflect, ReflectRef, Struct};
+use log::debug;
+use rusqlite::ToSql;
+use rusqlite::types::{ToSqlOutput, Value};
+use super::schema_gen::get_sql_literal;
+use crate::db::{aio_error::AioError, aio_query::{Next, Operator}, models::{AioModel, GenericValue, Schema}};
+
+/// Reflects the columns of a model with their `#[aio(...)]` attributes, skipped fields are left out and renamed fields use their column name.
+pub(crate) fn get_schema_from_generic<T:  Default + Struct + AioModel>() -> Vec<Schema> {  
+     let default_t = T::default();
+     let default_t2 = T::default();
+     let my_struct: Box<dyn Struct> = Box::new(default_t);
+     let model_info = T::model_info();
+
+     let ReflectRef::Struct(reflected) = default_t2.reflect_ref() else { unreachable!() };
+
+     let count = my_struct.iter_fields().count();
+     let mut schema_vec: Vec<Schema> = Vec::with_capacity(count);
+
+     for (i, field) in my_struct.iter_fields().enumerate() {
+          let field_name = reflected.name_at(i).unwrap();
+          let (field_type, nullable) = get_field_type(field);
+          debug!("Found field named '{}' of type '{}'", field_name, field_type);
+
+          let field_info = model_info.field(field_name).cloned().unwrap_or_default();
+
+          if field_info.skip {
+               continue;
+          }
+
+          let auto_increment = field_info.auto_increment;
+          let primary_key = auto_increment || field_info.primary_key;
+
+          // Without #[aio(default = ...)] the column defaults to the value of the field in T::default()
+          let default = match field_info.default {
+               Some(default) => Some(default),
+               None if !nullable && !primary_key => get_primitive_sql_value(field, &field_type).ok().and_then(|x| get_sql_literal(&x)),
+               None => None
+          };
+
+          schema_vec.push(Schema {
+               field_name: field_info.column_name.unwrap_or_else(|| field_name.into()),
+               field_type: field_type,
+               nullable: nullable,
+               primary_key: primary_key,
+               auto_increment: auto_increment,
+               unique: field_info.unique,
+               index: field_info.index,
+               default: default,
+               renamed_from: field_info.renamed_from,
+               hidden: false,
+               generated: false
+          });
+     }
+     
+     return schema_vec;
+}
+
+pub(crate) fn get_values_from_generic<'a, T:  Default + Struct + Clone>(value: &'a T) -> Vec<GenericValue<'a>> {  
+     let copied_value = value.clone();
+     let my_struct: Box<dyn Struct> = Box::new(copied_value);
+
+     let ReflectRef::Struct(reflected) = value.reflect_ref() else { unreachable!() };
+
+     let count = my_struct.iter_fields().count();
+     let mut schema_vec: Vec<GenericValue> = Vec::with_capacity(count);
+
+     for (i, field) in my_struct.iter_fields().enumerate() {
+          let field_name = reflected.name_at(i).unwrap();
+          let field_value = reflected.field_at(i).unwrap();
+          let (field_type, nullable) = get_field_type(field);
+          
+          debug!("Found field named '{}' with value '{:?}'", field_name, field_value);
+    
+          schema_vec.push(GenericValue {
+               field_name: field_name.into(),
+               field_value: field_value,
+               field_type: field_type,
+               nullable: nullable
+          });
+     }
+     
+     return schema_vec;
+}
+
+/// Returns the type ident of a field and whether it's an `Option`. For `Option<X>` the ident of `X` is returned, e.g. `String` for `Option<String>`.
+pub(crate) fn get_field_type(field: &dyn PartialReflect) -> (String, bool) {
+     let field_type = field.reflect_type_ident().unwrap_or_default();
+
+     if field_type != "Option" {
+          return (field_type.to_string(), false);
+     }
+
+     // e.g. core::option::Option<alloc::vec::Vec<u8>> => Vec
+     let type_path = field.reflect_type_path();
+     let inner_path = match (type_path.find('<'), type_path.rfind('>')) {
+          (Some(start), Some(end)) if start < end => &type_path[start + 1..end],
+          _ => type_path
+     };
+     let inner_path = inner_path.split('<').next().unwrap_or(inner_path);
+     let inner_type = inner_path.rsplit("::").next().unwrap_or(inner_path).trim();
+
+     return (inner_type.to_string(), true);
+}
+
+/// Resolves the name of the unique index that rejected a row from the constraint detail reported by SQLite (e.g. `Person.first_name, Person.last_name`).
+pub(crate) fn find_constraint_index(conn: &rusqlite::Connection, table_name: &str, constraint: &str) -> Option<String> {
+     let mut columns: Vec<&str> = constraint
+          .split(',')
+          .map(|x| x.trim())
+          .map(|x| x.rsplit('.').next().unwrap_or(x))
+          .collect();
+     columns.sort();
+
+     let mut stmt = conn.prepare(&format!("PRAGMA index_list({})", table_name)).ok()?;
+     let unique_indexes: Vec<String> = stmt
+          .query_map([], |row| Ok((row.get::<_, String>("name")?, row.get::<_, bool>("unique")?)))
+          .ok()?
+          .filter_map(|x| x.ok())
+          .filter(|(_, unique)| *unique)
+          .map(|(name, _)| name)
+          .collect();
+
+     for index_name in unique_indexes {
+          let mut stmt = conn.prepare(&format!("PRAGMA index_info({})", index_name)).ok()?;
+          let mut index_columns: Vec<String> = stmt
+               .query_map([], |row| row.get::<_, String>("name"))
+               .ok()?
+               .filter_map(|x| x.ok())
+               .collect();
+          index_columns.sort();
+
+          if index_columns == columns {
+               return Some(index_name);
+          }
+     }
+
+     return None;
+}
+
+pub(crate) fn get_next(next: &Next) -> String {
+     if next == &Next::And {
+          return "AND".into();
+     }
+     else {
+          return "OR".into();
+     }
+}
+
+/// Converts a reflected struct field into a SQLite value that can be bound to a `?` placeholder. `None` becomes NULL.
+pub(crate) fn get_sql_value(generic_value: &GenericValue) -> Result<Value, AioError> {
+     if !generic_value.nullable {
+          return get_primitive_sql_value(generic_value.field_value, &generic_value.field_type);
+     }
+
+     let ReflectRef::Enum(option) = generic_value.field_value.reflect_ref() else {
+          return Err(AioError::UnsupportedFieldType(generic_value.field_value.reflect_type_path().to_string()));
+     };
+
+     return match option.field_at(0) {
+          Some(inner_value) => get_primitive_sql_value(inner_value, &generic_value.field_type),
+          None => Ok(Value::Null)
+     };
+}
+
+fn get_primitive_sql_value(field_value: &dyn PartialReflect, field_type: &str) -> Result<Value, AioError> {
+     let unsupported = || AioError::UnsupportedFieldType(field_type.to_string());
+
+     let value = match field_type {
+          "bool" => Value::Integer(*field_value.try_downcast_ref::<bool>().ok_or_else(unsupported)? as i64),
+          "u8" => Value::Integer(*field_value.try_downcast_ref::<u8>().ok_or_else(unsupported)? as i64),
+          "u16" => Value::Integer(*field_value.try_downcast_ref::<u16>().ok_or_else(unsupported)? as i64),
+          "u32" => Value::Integer(*field_value.try_downcast_ref::<u32>().ok_or_else(unsupported)? as i64),
+          // Stored bit for bit, so values above i64::MAX round-trip as well.
+          "u64" => Value::Integer(*field_value.try_downcast_ref::<u64>().ok_or_else(unsupported)? as i64),
+          "i8" => Value::Integer(*field_value.try_downcast_ref::<i8>().ok_or_else(unsupported)? as i64),
+          "i16" => Value::Integer(*field_value.try_downcast_ref::<i16>().ok_or_else(unsupported)? as i64),
+          "i32" => Value::Integer(*field_value.try_downcast_ref::<i32>().ok_or_else(unsupported)? as i64),
+          "i64" => Value::Integer(*field_value.try_downcast_ref::<i64>().ok_or_else(unsupported)?),
+          "f32" => {
+               // Widen through the shortest decimal representation so 2.1f32 is stored (and compared) as 2.1
+               let value = *field_value.try_downcast_ref::<f32>().ok_or_else(unsupported)?;
+               Value::Real(value.to_string().parse::<f64>().unwrap_or(value as f64))
+          },
+          "f64" => Value::Real(*field_value.try_downcast_ref::<f64>().ok_or_else(unsupported)?),
+          "char" => Value::Text(field_value.try_downcast_ref::<char>().ok_or_else(unsupported)?.to_string()),
+          "String" => Value::Text(field_value.try_downcast_ref::<String>().ok_or_else(unsupported)?.clone()),
+          "Vec" => Value::Blob(field_value.try_downcast_ref::<Vec<u8>>().ok_or_else(unsupported)?.clone()),
+          _ => return Err(unsupported())
+     };
+
+     return Ok(value);
+}
+
+/// Converts an id passed by the caller into an owned SQLite value.
+pub(crate) fn to_sql_value(value: &dyn ToSql) -> Result<Value, AioError> {
+     return match value.to_sql()? {
+          ToSqlOutput::Borrowed(value_ref) => Ok(Value::from(value_ref)),
+          ToSqlOutput::Owned(value) => Ok(value),
+          other => Err(AioError::UnsupportedFieldType(format!("{:?}", other)))
+     };
+}
+
+/// Converts a query filter value into a SQLite value matching the type of the column, values that don't parse are compared as TEXT.
+pub(crate) fn get_sql_value_from_str(value: &str, field_type: &str) -> Value {
+     return match field_type {
+          "bool" => match value {
+               "true" | "1" => Value::Integer(1),
+               "false" | "0" => Value::Integer(0),
+               _ => Value::Text(value.to_string())
+          },
+          "u8" | "u16" | "u32" | "i8" | "i16" | "i32" | "i64" => match value.parse::<i64>() {
+               Ok(number) => Value::Integer(number),
+               Err(_) => Value::Text(value.to_string())
+          },
+          "u64" => match value.parse::<u64>() {
+               Ok(number) => Value::Integer(number as i64),
+               Err(_) => Value::Text(value.to_string())
+          },
+          "f32" | "f64" => match value.parse::<f64>() {
+               Ok(number) => Value::Real(number),
+               Err(_) => Value::Text(value.to_string())
+          },
+          _ => Value::Text(value.to_string())
+     };
+}
+
+/// Converts a partial update value into a SQLite value of the type of `field`, `None` sets an `Option` field to NULL.
+/// A value that can't be parsed as the type of the field is an `AioError::SchemaMismatch` instead of being stored as TEXT.
+pub(crate) fn parse_field_value(value: Option<&str>, field: &Schema) -> Result<Value, AioError> {
+     let Some(value) = value else {
+          if field.nullable {
+               return Ok(Value::Null);
+          }
+
+          return Err(AioError::SchemaMismatch(format!("{} isn't an Option and can't be set to NULL.", field.field_name)));
+     };
+
+     let mismatch = || AioError::SchemaMismatch(format!("{} isn't a valid {} value for {}.", value, field.field_type, field.field_name));
+
+     return match field.field_type.as_str() {
+          "bool" => match value {
+               "true" | "1" => Ok(Value::Integer(1)),
+               "false" | "0" => Ok(Value::Integer(0)),
+               _ => Err(mismatch())
+          },
+          "u8" => value.parse::<u8>().map(|x| Value::Integer(x.into())).map_err(|_| mismatch()),
+          "u16" => value.parse::<u16>().map(|x| Value::Integer(x.into())).map_err(|_| mismatch()),
+          "u32" => value.parse::<u32>().map(|x| Value::Integer(x.into())).map_err(|_| mismatch()),
+          // Stored bit for bit, like get_sql_value
+          "u64" => value.parse::<u64>().map(|x| Value::Integer(x as i64)).map_err(|_| mismatch()),
+          "i8" => value.parse::<i8>().map(|x| Value::Integer(x.into())).map_err(|_| mismatch()),
+          "i16" => value.parse::<i16>().map(|x| Value::Integer(x.into())).map_err(|_| mismatch()),
+          "i32" => value.parse::<i32>().map(|x| Value::Integer(x.into())).map_err(|_| mismatch()),
+          "i64" => value.parse::<i64>().map(Value::Integer).map_err(|_| mismatch()),
+          "f32" => value.parse::<f32>().and_then(|_| value.parse::<f64>()).map(Value::Real).map_err(|_| mismatch()),
+          "f64" => value.parse::<f64>().map(Value::Real).map_err(|_| mismatch()),
+          "char" => match value.chars().count() {
+               1 => Ok(Value::Text(value.to_string())),
+               _ => Err(mismatch())
+          },
+          "String" => Ok(Value::Text(value.to_string())),
+          "Vec" => hex::decode(value).map(Value::Blob).map_err(|_| mismatch()),
+          field_type => Err(AioError::UnsupportedFieldType(field_type.to_string()))
+     };
+}
+
+/// Escapes the `LIKE` wildcards so the value is matched literally. Used together with `ESCAPE '\'`.
+pub(crate) fn escape_like_value(value: &str) -> String {
+     return value
+          .replace('\\', "\\\\")
+          .replace('%', "\\%")
+          .replace('_', "\\_");
+}
+
+pub(crate) fn push_str_to_query_string(
+     query_string: &mut String, 
+     field_name: &str, 
+     last_item: bool, 
+     sql_operator: &str, 
+     next: Option<&Next>) {
+     if !last_item {
+          let next = next.unwrap();
+          let continuation = format!("{} {} ? {} ", field_name, sql_operator, get_next(next));
+          query_string.push_str(continuation.as_str());
+     } else {
+          let continuation = format!("{} {} ?", field_name, sql_operator);
+          query_string.push_str(continuation.as_str());
+     }
+}
+
+pub(crate) fn push_null_check_to_query_string(
+     query_string: &mut String, 
+     field_name: &str, 
+     last_item: bool, 
+     sql_operator: &str, 
+     next: Option<&Next>) {
+     if !last_item {
+          let next = next.unwrap();
+          let continuation = format!("{} {} {} ", field_name, sql_operator, get_next(next));
+          query_string.push_str(continuation.as_str());
+     } else {
+          let continuation = format!("{} {}", field_name, sql_operator);
+          query_string.push_str(continuation.as_str());
+     }
+}
+
+pub(crate) fn push_like_to_query_string(
+     query_string: &mut String, 
+     field_name: &str, 
+     last_item: bool, 
+     next: Option<&Next>) {
+     if !last_item {
+          let next = next.unwrap();
+          let continuation = format!("{} LIKE ? ESCAPE '\\' {} ", field_name, get_next(next));
+          query_string.push_str(continuation.as_str());
+     } else {
+          let continuation = format!("{} LIKE ? ESCAPE '\\'", field_name);
+          query_string.push_str(continuation.as_str());
+     }
+}
+
+pub(crate) fn query_match_operators(
+     operator: &Operator, 
+     query_string: &mut String, 
+     params: &mut Vec<Value>,
+     field_name: &str, 
+     field_type: &str, 
+     last_item: bool, 
+     next: Option<&Next>) {
+     match operator {
+          Operator::Eq(value) => {
+               params.push(get_sql_value_from_str(value, field_type));
+               push_str_to_query_string(query_string, field_name, last_item, "==", next);
+          },
+          Operator::Ne(value) => {
+               params.push(get_sql_value_from_str(value, field_type));
+               push_str_to_query_string(query_string, field_name, last_item, "<>", next);
+          },
+          Operator::Gt(value) => {
+               params.push(get_sql_value_from_str(value, field_type));
+               push_str_to_query_string(query_string, field_name, last_item, ">", next);
+          },
+          Operator::Lt(value) => {
+               params.push(get_sql_value_from_str(value, field_type));
+               push_str_to_query_string(query_string, field_name, last_item, "<", next);
+          },
+          Operator::Ge(value) => {
+               params.push(get_sql_value_from_str(value, field_type));
+               push_str_to_query_string(query_string, field_name, last_item, ">=", next);
+          },
+          Operator::Le(value) => {
+               params.push(get_sql_value_from_str(value, field_type));
+               push_str_to_query_string(query_string, field_name, last_item, "<=", next);
+          },
+          Operator::Contains(value) => {
+               params.push(Value::Text(format!("%{}%", escape_like_value(value))));
+               push_like_to_query_string(query_string, field_name, last_item, next);
+          },
+          Operator::StartsWith(value) => {
+               params.push(Value::Text(format!("{}%", escape_like_value(value))));
+               push_like_to_query_string(query_string, field_name, last_item, next);
+          },
+          Operator::EndsWith(value) => {
+               params.push(Value::Text(format!("%{}", escape_like_value(value))));
+               push_like_to_query_string(query_string, field_name, last_item, next);
+          },
+          Operator::IsNull => {
+               push_null_check_to_query_string(query_string, field_name, last_item, "IS NULL", next);
+          },
+          Operator::IsNotNull => {
+               push_null_check_to_query_string(query_string, field_name, last_item, "IS NOT NULL", next);
+          }
+     }
+}
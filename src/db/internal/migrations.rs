@@ -0,0 +1,693 @@
+use std::any::TypeId;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy_reflect::Struct;
+use log::{debug, info, trace};
+use rusqlite::{params_from_iter, types::Value, OptionalExtension};
+use tokio::sync::OwnedMutexGuard;
+use tokio_rusqlite::Connection;
+
+use crate::db::{aio_database::{AioDatabase, ConnectionState}, aio_error::AioError, aio_migration::{ColumnBackfill, ColumnConverter, MigrationAction, MigrationStep, Migrator, ModelUpgrade}, aio_transaction::{AioTransaction, TransactionMode}, aio_query::map_row_to_struct, models::{AioModel, AppliedMigration, ColumnChange, ColumnRename, MigrationPolicy, MigrationReport, ModelInfo, Schema}};
+use super::queries::{alter_table_drop_column, alter_table_new_column, alter_table_rename_column, create_field_indexes, create_table, get_current_db_schema};
+use super::helpers::{get_schema_from_generic, get_sql_value, get_values_from_generic};
+use super::schema_gen::{generate_db_schema_query, get_column_definition, get_sql_type};
+use super::statements::generate_insert_query;
+
+/// Compares the model with the existing table, `current_schema` is None if the table doesn't exist.
+pub(crate) fn plan_migration(name: &str, generic_schema: &[Schema], current_schema: Option<&[Schema]>, policy: MigrationPolicy) -> MigrationReport {
+     let mut report = MigrationReport { table_name: name.to_string(), ..Default::default() };
+
+     let Some(current_schema) = current_schema else {
+          report.created = true;
+          return report;
+     };
+
+     // Hidden and generated columns can't be written, they are left as they are
+     let current_schema: Vec<&Schema> = current_schema.iter().filter(|x| !x.hidden && !x.generated).collect();
+     let has_column = |column_name: &str| current_schema.iter().any(|x| x.field_name == column_name);
+
+     for generic_field in generic_schema.iter().filter(|x| !has_column(&x.field_name)) {
+          let renamed_from = generic_field.renamed_from.iter()
+               .find(|x| has_column(x) && !generic_schema.iter().any(|field| field.field_name == **x));
+
+          match renamed_from {
+               Some(from) => report.renamed.push(ColumnRename { from: from.clone(), to: generic_field.field_name.clone() }),
+               None => report.added.push(generic_field.clone())
+          }
+     }
+
+     for current in current_schema.iter() {
+          let renamed_to = report.renamed.iter().find(|x| x.from == current.field_name).map(|x| x.to.as_str());
+          let column_name = renamed_to.unwrap_or(&current.field_name);
+
+          let Some(generic_field) = generic_schema.iter().find(|x| x.field_name == column_name) else {
+               if policy == MigrationPolicy::AdditiveOnly {
+                    report.kept.push(current.field_name.clone());
+               } else {
+                    report.dropped.push(current.field_name.clone());
+               }
+               continue;
+          };
+
+          let Some(to_type) = get_column_type(generic_field) else {
+               continue;
+          };
+
+          if !column_type_matches(&to_type, &current.field_type) {
+               report.changed.push(ColumnChange {
+                    column_name: generic_field.field_name.clone(),
+                    from_type: current.field_type.clone(),
+                    to_type: to_type
+               });
+          }
+     }
+
+     for removed in report.dropped.iter().chain(report.kept.iter()) {
+          let removed_type = &current_schema.iter().find(|x| x.field_name == *removed).unwrap().field_type;
+
+          for added in report.added.iter() {
+               if get_column_type(added).is_some_and(|x| column_type_matches(&x, removed_type)) {
+                    report.possible_renames.push(ColumnRename { from: removed.clone(), to: added.field_name.clone() });
+               }
+          }
+     }
+
+     return report;
+}
+
+/// Creates the table or migrates it to the model according to the policy of `migrator`.
+pub(crate) async fn migrate(name: &str, generic_schema: &[Schema], migrator: &Migrator, connection: &Connection) -> Result<MigrationReport, AioError> {
+     let current_schema = get_current_db_schema(name, connection).await?;
+     debug!("Current Db schema: {:?}", current_schema);
+
+     let mut report = plan_migration(name, generic_schema, current_schema.as_deref(), migrator.policy);
+
+     match migrator.policy {
+          MigrationPolicy::DryRun => return Ok(report),
+          MigrationPolicy::Fail if report.has_changes() => return Err(AioError::MigrationRequired(Box::new(report))),
+          _ => { }
+     }
+
+     // Foreign keys can't be switched off inside a transaction, a rebuild drops the old table while they're off
+     let foreign_keys = connection.call(|conn| {
+          let foreign_keys: bool = conn.pragma_query_value(None, "foreign_keys", |row| row.get(0))?;
+          conn.pragma_update(None, "foreign_keys", false)?;
+          conn.execute_batch("BEGIN IMMEDIATE")?;
+          Ok(foreign_keys)
+     }).await?;
+
+     let result = apply_migration(name, generic_schema, &report, migrator, connection).await;
+     let succeeded = result.is_ok();
+
+     let finished = connection.call(move |conn| {
+          let committed = match succeeded {
+               true => conn.execute_batch("COMMIT"),
+               false => Ok(())
+          };
+
+          if !conn.is_autocommit() {
+               conn.execute_batch("ROLLBACK")?;
+          }
+
+          conn.pragma_update(None, "foreign_keys", foreign_keys)?;
+          Ok(committed?)
+     }).await;
+
+     result?;
+     finished?;
+
+     report.applied = true;
+
+     return Ok(report);
+}
+
+/// Runs the changes of `report` in the transaction of `migrate`.
+async fn apply_migration(name: &str, generic_schema: &[Schema], report: &MigrationReport, migrator: &Migrator, connection: &Connection) -> Result<(), AioError> {
+     if report.created {
+          debug!("Creating table {} with schema: {:?}", name, generic_schema);
+          create_table(generic_schema, name, connection).await?;
+     }
+
+     for rename in report.renamed.iter() {
+          info!("Renaming column: {} to {}", rename.from, rename.to);
+          alter_table_rename_column(name, &rename.from, &rename.to, connection).await?;
+     }
+
+     for column_name in report.dropped.iter() {
+          info!("Dropping column: {}", column_name);
+          alter_table_drop_column(name, column_name, connection).await?;
+     }
+
+     for column_name in report.kept.iter() {
+          info!("Keeping column: {}, it isn't part of the model anymore", column_name);
+     }
+
+     for generic_field in report.added.iter() {
+          info!("Adding column: {} as {}", generic_field.field_name.as_str(), generic_field.field_type.as_str());
+          alter_table_new_column(name, generic_field, connection).await?;
+     }
+
+     for rename in report.possible_renames.iter() {
+          info!("Column {} might have been renamed to {}, add #[aio(renamed_from = \"{}\")] to keep its data", rename.from, rename.to, rename.from);
+     }
+
+     if !report.changed.is_empty() {
+          for change in report.changed.iter() {
+               info!("Changing type of column: {} from {} to {}", change.column_name, change.from_type, change.to_type);
+          }
+
+          rebuild_table(name, generic_schema, &report.changed, migrator, connection).await?;
+     }
+
+     create_field_indexes(generic_schema, name, connection).await?;
+
+     return Ok(());
+}
+
+/// Changes the type of columns with SQLite's table rebuild procedure, in a savepoint of the migration: a new table is created,
+/// the rows are copied with `CAST(column AS type)` or the converter of the column, the old table is dropped and the new one renamed.
+async fn rebuild_table(name: &str, generic_schema: &[Schema], changes: &[ColumnChange], migrator: &Migrator, connection: &Connection) -> Result<(), AioError> {
+     let current_schema = get_current_db_schema(name, connection).await?
+          .ok_or_else(|| AioError::SchemaMismatch(format!("Table {} doesn't exist.", name)))?;
+
+     if let Some(generated) = current_schema.iter().find(|x| x.generated || x.hidden) {
+          return Err(AioError::SchemaMismatch(format!("Table {} can't be rebuilt, column {} is generated or hidden.", name, generated.field_name)));
+     }
+
+     let rebuild_name = format!("{}_aio_rebuild", name);
+     let mut column_definitions = Vec::with_capacity(current_schema.len());
+     let mut column_names = Vec::with_capacity(current_schema.len());
+     let mut select_columns = Vec::with_capacity(current_schema.len());
+     let mut converters = Vec::with_capacity(current_schema.len());
+
+     for current in current_schema.iter() {
+          // Columns that aren't part of the model anymore keep their definition
+          let column_definition = match generic_schema.iter().find(|x| x.field_name == current.field_name) {
+               Some(generic_field) => get_column_definition(generic_field)?,
+               None => get_stored_column_definition(current)
+          };
+
+          let change = changes.iter().find(|x| x.column_name == current.field_name);
+          let converter = change.and_then(|x| migrator.converters.get(&x.column_name)).cloned();
+
+          let select_column = match change {
+               Some(change) if converter.is_none() => format!("CAST({} AS {})", current.field_name, change.to_type),
+               _ => current.field_name.clone()
+          };
+
+          column_definitions.push(column_definition);
+          column_names.push(current.field_name.clone());
+          select_columns.push(select_column);
+          converters.push(converter);
+     }
+
+     let create_query = format!("CREATE TABLE {} ({})", rebuild_name, column_definitions.join(", "));
+     let select_query = format!("SELECT {} FROM {}", select_columns.join(", "), name);
+     let insert_query = generate_insert_query(&rebuild_name, &column_names);
+     let name = name.to_string();
+
+     trace!("Rebuilding table {} with: {}", name, create_query);
+
+     connection.call(move |conn| {
+          Ok(copy_to_new_table(conn, &name, &rebuild_name, &create_query, &select_query, &insert_query, &converters))
+     }).await??;
+
+     return Ok(());
+}
+
+fn copy_to_new_table(
+     conn: &mut rusqlite::Connection,
+     name: &str,
+     rebuild_name: &str,
+     create_query: &str,
+     select_query: &str,
+     insert_query: &str,
+     converters: &[Option<ColumnConverter>]) -> Result<(), AioError> {
+     let transaction = conn.savepoint()?;
+
+     let index_queries = transaction
+          .prepare("SELECT sql FROM sqlite_schema WHERE type = 'index' AND tbl_name = ?1 AND sql IS NOT NULL")?
+          .query_map([name], |row| row.get::<_, String>(0))?
+          .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+
+     transaction.execute(create_query, [])?;
+
+     if converters.iter().all(|x| x.is_none()) {
+          transaction.execute(&format!("INSERT INTO {} {}", rebuild_name, select_query), [])?;
+     } else {
+          let mut select = transaction.prepare(select_query)?;
+          let mut insert = transaction.prepare(insert_query)?;
+          let mut rows = select.query([])?;
+
+          while let Some(row) = rows.next()? {
+               let mut values = Vec::with_capacity(converters.len());
+
+               for (index, converter) in converters.iter().enumerate() {
+                    let value: Value = row.get(index)?;
+
+                    match converter {
+                         Some(converter) if value != Value::Null => values.push(converter(value)?),
+                         _ => values.push(value)
+                    }
+               }
+
+               insert.execute(params_from_iter(values))?;
+          }
+     }
+
+     transaction.execute(&format!("DROP TABLE {}", name), [])?;
+     transaction.execute(&format!("ALTER TABLE {} RENAME TO {}", rebuild_name, name), [])?;
+
+     for index_query in index_queries.iter() {
+          transaction.execute(index_query, [])?;
+     }
+
+     transaction.commit()?;
+
+     return Ok(());
+}
+
+fn column_type_matches(sql_type: &str, stored_type: &str) -> bool {
+     return sql_type.split_whitespace().eq(stored_type.split_whitespace().map(|x| x.to_uppercase()));
+}
+
+fn get_stored_column_definition(current: &Schema) -> String {
+     let mut column_definition = format!("{} {}", current.field_name, current.field_type);
+
+     if !current.nullable {
+          column_definition.push_str(" NOT NULL");
+     }
+
+     if let Some(default) = current.default.as_ref() {
+          column_definition.push_str(" DEFAULT ");
+          column_definition.push_str(default);
+     }
+
+     return column_definition;
+}
+
+fn get_column_type(field: &Schema) -> Option<String> {
+     if field.auto_increment {
+          return Some("INTEGER".into());
+     }
+
+     return get_sql_type(field.field_type.as_str());
+}
+
+/// Checks that the backfills of the columns of the model were registered for **T**, before the table is changed.
+pub(crate) fn check_backfills<T: 'static>(generic_schema: &[Schema], migrator: &Migrator) -> Result<(), AioError> {
+     for field in generic_schema.iter() {
+          if let Some((type_id, _)) = migrator.backfills.get(&field.field_name)
+               && *type_id != TypeId::of::<T>() {
+               return Err(AioError::SchemaMismatch(format!("The backfill of column {} was registered for another model.", field.field_name)));
+          }
+     }
+
+     return Ok(());
+}
+
+/// Fills the added columns that have a `Migrator::backfill` callback, reading every existing row as **T**. Returns the backfilled columns.
+/// The caller holds the connection lock.
+pub(crate) async fn backfill_columns<T: Default + Struct + Clone>(db: &AioDatabase, migrator: &Migrator, added: &[Schema]) -> Result<Vec<String>, AioError> {
+     let backfills: Vec<(String, ColumnBackfill)> = added.iter()
+          .filter_map(|x| migrator.backfills.get(&x.field_name).map(|(_, backfill)| (x.field_name.clone(), backfill.clone())))
+          .collect();
+
+     if backfills.is_empty() {
+          return Ok(Vec::new());
+     }
+
+     let table = db.own_table()?;
+     let name = table.name.clone();
+     let model_info = table.statements.model_info.clone();
+     let columns: Vec<String> = backfills.iter().map(|(column_name, _)| column_name.clone()).collect();
+
+     info!("Backfilling columns: {:?}", columns);
+
+     db.conn.sqlite_connection.call(move |conn| {
+          Ok(backfill_rows::<T>(conn, &name, &backfills, &model_info))
+     }).await??;
+
+     return Ok(columns);
+}
+
+fn backfill_rows<T: Default + Struct + Clone>(conn: &mut rusqlite::Connection, name: &str, backfills: &[(String, ColumnBackfill)], model_info: &ModelInfo) -> Result<(), AioError> {
+     let transaction = conn.transaction()?;
+     let mut updates = Vec::new();
+
+     {
+          let mut select = transaction.prepare(&format!("SELECT *, rowid AS _aio_rowid FROM {}", name))?;
+          let mut rows = select.query([])?;
+
+          while let Some(row) = rows.next()? {
+               let value = map_row_to_struct::<T>(row, model_info)?;
+               let mut values = Vec::with_capacity(backfills.len() + 1);
+
+               for (_, backfill) in backfills.iter() {
+                    values.push(backfill(&value)?);
+               }
+
+               values.push(Value::Integer(row.get("_aio_rowid")?));
+               updates.push(values);
+          }
+     }
+
+     let set_columns: Vec<String> = backfills.iter().map(|(column_name, _)| format!("{} = ?", column_name)).collect();
+     let update_query = format!("UPDATE {} SET {} WHERE rowid = ?", name, set_columns.join(", "));
+
+     {
+          let mut update = transaction.prepare(&update_query)?;
+
+          for values in updates {
+               update.execute(params_from_iter(values))?;
+          }
+     }
+
+     transaction.commit()?;
+
+     return Ok(());
+}
+
+/// The table recording the model version of each table.
+pub(crate) const MODEL_VERSIONS_TABLE: &str = "_aio_model_versions";
+
+/// Reads the recorded model version of a table, 0 if none was recorded.
+pub(crate) async fn read_model_version(name: &str, connection: &Connection) -> Result<u32, AioError> {
+     let name = name.to_string();
+
+     let version = connection.call(move |conn| {
+          let exists: bool = conn.query_row("SELECT EXISTS (SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?1)", [MODEL_VERSIONS_TABLE], |row| row.get(0))?;
+
+          if !exists {
+               return Ok(0);
+          }
+
+          let version: Option<u32> = conn
+               .query_row(&format!("SELECT version FROM {} WHERE table_name = ?1", MODEL_VERSIONS_TABLE), [&name], |row| row.get(0))
+               .optional()?;
+
+          Ok(version.unwrap_or_default())
+     }).await?;
+
+     return Ok(version);
+}
+
+/// Records the model version of a table.
+pub(crate) async fn write_model_version(name: &str, version: u32, connection: &Connection) -> Result<(), AioError> {
+     let name = name.to_string();
+
+     connection.call(move |conn| {
+          create_model_versions(conn)?;
+          set_model_version(conn, &name, version)?;
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+/// Runs the upgrades of `migrator` between the recorded model version of the table and `model_version`, each in a transaction together with the new version.
+/// Returns the versions the table was upgraded to, or would be with `MigrationPolicy::DryRun`.
+pub(crate) async fn upgrade_table(name: &str, model_version: u32, migrator: &Migrator, connection: &Connection) -> Result<Vec<u32>, AioError> {
+     if get_current_db_schema(name, connection).await?.is_none() {
+          return Ok(Vec::new());
+     }
+
+     let table_version = read_model_version(name, connection).await?;
+
+     if table_version > model_version {
+          return Err(AioError::MigrationHistoryDiverged {
+               version: table_version,
+               message: format!("table {} is at model version {}, the model is at version {}.", name, table_version, model_version)
+          });
+     }
+
+     let upgrades: Vec<ModelUpgrade> = migrator.upgrades.iter()
+          .filter(|x| x.version > table_version && x.version <= model_version)
+          .cloned()
+          .collect();
+
+     let versions: Vec<u32> = upgrades.iter().map(|x| x.version).collect();
+
+     if migrator.policy == MigrationPolicy::DryRun {
+          return Ok(versions);
+     }
+
+     for upgrade in upgrades {
+          info!("Upgrading table {} to model version {}", name, upgrade.version);
+          let name = name.to_string();
+
+          let rows = connection.call(move |conn| {
+               let foreign_keys: bool = conn.pragma_query_value(None, "foreign_keys", |row| row.get(0))?;
+               conn.pragma_update(None, "foreign_keys", false)?;
+
+               let result = run_upgrade(conn, &name, &upgrade);
+
+               conn.pragma_update(None, "foreign_keys", foreign_keys)?;
+               Ok(result)
+          }).await??;
+
+          debug!("Upgraded {} rows", rows);
+     }
+
+     return Ok(versions);
+}
+
+fn run_upgrade(conn: &mut rusqlite::Connection, name: &str, upgrade: &ModelUpgrade) -> Result<usize, AioError> {
+     let transaction = conn.transaction()?;
+
+     create_model_versions(&transaction)?;
+     let rows = (upgrade.upgrade)(&transaction, name)?;
+     set_model_version(&transaction, name, upgrade.version)?;
+
+     transaction.commit()?;
+
+     return Ok(rows);
+}
+
+/// Streams the rows of the table as **Old** through `upgrade` into a new table with the columns of **New**, then replaces the table with it.
+/// Indexes that still apply to the new columns are recreated. Returns the number of rows.
+pub(crate) fn upgrade_rows<Old, New, F>(conn: &rusqlite::Connection, name: &str, upgrade: &F) -> Result<usize, AioError>
+where Old: Default + Struct + Clone + AioModel, New: Default + Struct + Clone + AioModel, F: Fn(Old) -> New {
+     let old_info = Old::model_info();
+     let new_info = New::model_info();
+     let new_schema = get_schema_from_generic::<New>();
+
+     let upgrade_name = format!("{}_aio_upgrade", name);
+     let column_names: Vec<&str> = new_schema.iter().map(|x| x.field_name.as_str()).collect();
+
+     let index_queries = conn
+          .prepare("SELECT sql FROM sqlite_schema WHERE type = 'index' AND tbl_name = ?1 AND sql IS NOT NULL")?
+          .query_map([name], |row| row.get::<_, String>(0))?
+          .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+
+     conn.execute(&generate_db_schema_query(&new_schema, &upgrade_name)?, [])?;
+
+     let mut rows = 0;
+
+     {
+          let mut select = conn.prepare(&format!("SELECT * FROM {}", name))?;
+          let mut insert = conn.prepare(&generate_insert_query(&upgrade_name, &column_names))?;
+          let mut old_rows = select.query([])?;
+
+          while let Some(row) = old_rows.next()? {
+               let new_value = upgrade(map_row_to_struct::<Old>(row, &old_info)?);
+
+               let values = get_values_from_generic(&new_value).iter()
+                    .filter(|x| new_info.column_name(&x.field_name).is_some())
+                    .map(get_sql_value)
+                    .collect::<Result<Vec<Value>, AioError>>()?;
+
+               insert.execute(params_from_iter(values))?;
+               rows += 1;
+          }
+     }
+
+     conn.execute(&format!("DROP TABLE {}", name), [])?;
+     conn.execute(&format!("ALTER TABLE {} RENAME TO {}", upgrade_name, name), [])?;
+
+     for index_query in index_queries.iter() {
+          // A failed statement doesn't abort the transaction
+          if let Err(error) = conn.execute(index_query, []) {
+               info!("Index not recreated after the upgrade: {} ({})", index_query, error);
+          }
+     }
+
+     return Ok(rows);
+}
+
+fn create_model_versions(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+     conn.execute(&format!("CREATE TABLE IF NOT EXISTS {} (table_name TEXT PRIMARY KEY, version INTEGER NOT NULL)", MODEL_VERSIONS_TABLE), [])?;
+     return Ok(());
+}
+
+fn set_model_version(conn: &rusqlite::Connection, name: &str, version: u32) -> Result<(), rusqlite::Error> {
+     conn.execute(&format!("INSERT INTO {} (table_name, version) VALUES (?1, ?2) ON CONFLICT (table_name) DO UPDATE SET version = excluded.version", MODEL_VERSIONS_TABLE), rusqlite::params![name, version])?;
+     return Ok(());
+}
+
+/// The table recording the versioned steps that were applied.
+pub(crate) const MIGRATIONS_TABLE: &str = "_aio_migrations";
+
+/// Reads the applied versioned steps of the table `name`, or of every table if `None`, ordered by table and version. Empty if `_aio_migrations` doesn't exist.
+pub(crate) async fn read_migration_history(name: Option<&str>, connection: &Connection) -> Result<Vec<AppliedMigration>, AioError> {
+     let name = name.map(|x| x.to_string());
+
+     let history = connection.call(move |conn| {
+          let exists: bool = conn.query_row("SELECT EXISTS (SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?1)", [MIGRATIONS_TABLE], |row| row.get(0))?;
+
+          if !exists {
+               return Ok(Vec::new());
+          }
+
+          let mut stmt = conn.prepare(&format!("SELECT table_name, version, name, checksum, applied_at FROM {} WHERE ?1 IS NULL OR table_name = ?1 ORDER BY table_name, version", MIGRATIONS_TABLE))?;
+          let history = stmt
+               .query_map([name], |row| {
+                    Ok(AppliedMigration {
+                         table_name: row.get(0)?,
+                         version: row.get(1)?,
+                         name: row.get(2)?,
+                         checksum: row.get(3)?,
+                         applied_at: row.get(4)?
+                    })
+               })?
+               .collect::<Result<Vec<AppliedMigration>, rusqlite::Error>>()?;
+
+          Ok(history)
+     }).await?;
+
+     return Ok(history);
+}
+
+/// Checks the recorded history against the steps and returns the steps that weren't applied yet.
+/// Fails if a version is registered twice, a recorded step is missing or changed, or a pending step comes before the last applied one.
+pub(crate) fn pending_steps<'a>(steps: &'a [MigrationStep], history: &[AppliedMigration]) -> Result<Vec<&'a MigrationStep>, AioError> {
+     if let Some(duplicate) = steps.windows(2).find(|x| x[0].version == x[1].version) {
+          return Err(diverged(duplicate[0].version, "the version is registered more than once."));
+     }
+
+     for applied in history.iter() {
+          let Some(step) = steps.iter().find(|x| x.version == applied.version) else {
+               return Err(diverged(applied.version, &format!("step {} was applied but isn't registered.", applied.name)));
+          };
+
+          if step.name != applied.name || step.checksum() != applied.checksum {
+               return Err(diverged(applied.version, &format!("step {} was applied as {} with checksum {}, it's now {} with checksum {}.", applied.version, applied.name, applied.checksum, step.name, step.checksum())));
+          }
+     }
+
+     let last_applied = history.iter().map(|x| x.version).max();
+     let pending: Vec<&MigrationStep> = steps.iter().filter(|x| !history.iter().any(|applied| applied.version == x.version)).collect();
+
+     if let (Some(last_applied), Some(first_pending)) = (last_applied, pending.first())
+          && first_pending.version < last_applied {
+          return Err(diverged(first_pending.version, &format!("step {} isn't applied but version {} is.", first_pending.name, last_applied)));
+     }
+
+     return Ok(pending);
+}
+
+/// Runs the pending steps of the migrator on the table of the handle, each in an `IMMEDIATE` transaction together with its record in `_aio_migrations`.
+/// The transactions reuse the connection reserved by `guard`, which is handed back once every step is applied.
+pub(crate) async fn run_migration_steps(db: &AioDatabase, migrator: &Migrator, mut guard: OwnedMutexGuard<ConnectionState>) -> Result<(Vec<u32>, OwnedMutexGuard<ConnectionState>), AioError> {
+     if migrator.steps.is_empty() {
+          return Ok((Vec::new(), guard));
+     }
+
+     create_migration_history(db).await?;
+
+     let name = db.own_table()?.name.clone();
+     let history = read_migration_history(Some(&name), &db.conn.sqlite_connection).await?;
+     let pending = pending_steps(&migrator.steps, &history)?;
+     let mut applied_versions = Vec::with_capacity(pending.len());
+
+     for step in pending {
+          info!("Running migration step {}: {}", step.version, step.name);
+
+          let insert_query = format!("INSERT INTO {} (table_name, version, name, checksum, applied_at) VALUES (?, ?, ?, ?, ?)", MIGRATIONS_TABLE);
+          let params = vec![
+               Value::Text(name.clone()),
+               Value::Integer(step.version as i64),
+               Value::Text(step.name.clone()),
+               Value::Text(step.checksum()),
+               Value::Integer(unix_timestamp())
+          ];
+
+          guard = run_in_transaction(db, guard, &step.up, &insert_query, params).await?;
+          applied_versions.push(step.version);
+     }
+
+     return Ok((applied_versions, guard));
+}
+
+/// Reverts the applied steps of the table of the handle with a version greater than `version`, newest first, each in an `IMMEDIATE` transaction together with the removal of its record.
+/// The transactions reuse the connection reserved by `guard`.
+pub(crate) async fn revert_migration_steps(db: &AioDatabase, migrator: &Migrator, version: u32, mut guard: OwnedMutexGuard<ConnectionState>) -> Result<Vec<u32>, AioError> {
+     let name = db.own_table()?.name.clone();
+     let history = read_migration_history(Some(&name), &db.conn.sqlite_connection).await?;
+     pending_steps(&migrator.steps, &history)?;
+
+     let mut reverted_versions = Vec::new();
+
+     for applied in history.iter().rev().filter(|x| x.version > version) {
+          let step = migrator.steps.iter().find(|x| x.version == applied.version).unwrap();
+
+          let Some(down) = step.down.as_ref() else {
+               return Err(AioError::Other(format!("Migration step {} ({}) has no down step.", step.version, step.name)));
+          };
+
+          info!("Reverting migration step {}: {}", step.version, step.name);
+
+          let delete_query = format!("DELETE FROM {} WHERE table_name = ? AND version = ?", MIGRATIONS_TABLE);
+          let params = vec![Value::Text(name.clone()), Value::Integer(step.version as i64)];
+
+          guard = run_in_transaction(db, guard, down, &delete_query, params).await?;
+          reverted_versions.push(step.version);
+     }
+
+     return Ok(reverted_versions);
+}
+
+async fn create_migration_history(db: &AioDatabase) -> Result<(), AioError> {
+     let query = format!("CREATE TABLE IF NOT EXISTS {} (table_name TEXT NOT NULL, version INTEGER NOT NULL, name TEXT NOT NULL, checksum TEXT NOT NULL, applied_at INTEGER NOT NULL, PRIMARY KEY (table_name, version))", MIGRATIONS_TABLE);
+
+     db.conn.sqlite_connection.call(move |conn| {
+          conn.execute(&query, [])?;
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+/// Runs the action and the query recording it in one `IMMEDIATE` transaction on the reserved connection. The connection is released if either fails.
+async fn run_in_transaction(db: &AioDatabase, guard: OwnedMutexGuard<ConnectionState>, action: &MigrationAction, record_query: &str, params: Vec<Value>) -> Result<OwnedMutexGuard<ConnectionState>, AioError> {
+     let transaction = AioTransaction::begin_locked(db, TransactionMode::Immediate, guard).await?;
+
+     let result = match run_action(&transaction, action).await {
+          Ok(()) => transaction.execute(record_query, params).await.map(drop),
+          Err(error) => Err(error)
+     };
+
+     if let Err(error) = result {
+          if transaction.is_active() {
+               transaction.rollback().await?;
+          }
+          return Err(error);
+     }
+
+     return transaction.commit_locked().await;
+}
+
+async fn run_action(transaction: &AioTransaction<'_>, action: &MigrationAction) -> Result<(), AioError> {
+     return match action {
+          MigrationAction::Sql(sql) => transaction.execute_batch(sql).await,
+          MigrationAction::Function(function) => function(transaction.clone()).await
+     };
+}
+
+fn diverged(version: u32, message: &str) -> AioError {
+     return AioError::MigrationHistoryDiverged { version: version, message: message.to_string() };
+}
+
+fn unix_timestamp() -> i64 {
+     return SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs() as i64).unwrap_or_default();
+}
@@ -1,3 +1,6 @@
-pub(crate) mod schema_gen;
-pub(crate) mod helpers;
-pub(crate) mod queries;
\ No newline at end of file
+pub(crate) mod schema_gen;
+pub(crate) mod helpers;
+pub(crate) mod queries;
+pub(crate) mod statements;
+pub(crate) mod migrations;
+pub(crate) mod pool;
\ No newline at end of file
@@ -0,0 +1,196 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use rusqlite::OpenFlags;
+use tokio::sync::{OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+use tokio_rusqlite::Connection as SqliteConnection;
+
+use crate::db::aio_database::{AioDatabaseConnection, ConnectionState};
+use crate::db::aio_error::AioError;
+use crate::db::TempStore;
+use super::statements::StatementCache;
+
+/// Default number of read-only connections of a file database in WAL mode.
+pub(crate) const DEFAULT_READER_POOL_SIZE: usize = 4;
+
+/// A read-only connection of the pool and the mirror of its prepared statement cache.
+pub(crate) struct Reader {
+     pub(crate) sqlite_connection: SqliteConnection,
+     pub(crate) statement_cache: Arc<StatementCache>
+}
+
+/// The settings of a connection that the readers share with the writer, applied when a reader is opened and when they change.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReaderSettings {
+     pub(crate) busy_timeout: Duration,
+     pub(crate) cache_size: Option<i64>,
+     pub(crate) mmap_size: Option<u64>,
+     pub(crate) temp_store: TempStore
+}
+
+impl ReaderSettings {
+     pub(crate) fn apply(&self, conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+          conn.busy_timeout(self.busy_timeout)?;
+          conn.pragma_update(None, "temp_store", self.temp_store.to_string())?;
+
+          if let Some(cache_size) = self.cache_size {
+               conn.pragma_update(None, "cache_size", cache_size)?;
+          }
+
+          if let Some(mmap_size) = self.mmap_size {
+               conn.pragma_update(None, "mmap_size", mmap_size as i64)?;
+          }
+
+          return Ok(());
+     }
+}
+
+/// Read-only connections to the database file, each running one query at a time.
+pub(crate) struct ReaderPool {
+     pub(crate) readers: Vec<Reader>,
+     idle: Mutex<Vec<usize>>,
+     permits: Arc<Semaphore>
+}
+
+/// A reader taken from the pool, given back when dropped.
+pub(crate) struct PooledReader {
+     pool: Arc<ReaderPool>,
+     index: usize,
+     _permit: OwnedSemaphorePermit
+}
+
+impl ReaderPool {
+     /// Opens `size` read-only connections to `path`, with a statement cache of `capacity` and `settings`.
+     pub(crate) async fn open(path: &Path, size: usize, capacity: usize, settings: ReaderSettings) -> Result<ReaderPool, AioError> {
+          let mut readers = Vec::with_capacity(size);
+
+          for _ in 0..size {
+               let sqlite_connection = SqliteConnection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX).await?;
+
+               sqlite_connection.call(move |conn| {
+                    conn.set_prepared_statement_cache_capacity(capacity);
+                    Ok(settings.apply(conn)?)
+               }).await?;
+
+               readers.push(Reader {
+                    sqlite_connection: sqlite_connection,
+                    statement_cache: Arc::new(StatementCache::new(capacity))
+               });
+          }
+
+          return Ok(ReaderPool {
+               readers: readers,
+               idle: Mutex::new((0..size).collect()),
+               permits: Arc::new(Semaphore::new(size))
+          });
+     }
+
+     /// Applies changed `settings` to every reader.
+     pub(crate) async fn apply(&self, settings: ReaderSettings) -> Result<(), AioError> {
+          for reader in self.readers.iter() {
+               reader.sqlite_connection.call(move |conn| Ok(settings.apply(conn)?)).await?;
+          }
+
+          return Ok(());
+     }
+
+     pub(crate) fn idle(&self) -> usize {
+          return self.idle.lock().unwrap().len();
+     }
+
+     /// Waits for an idle reader, the wait is recorded in `waits`. None if the pool was closed in the meantime.
+     pub(crate) async fn acquire(self: Arc<ReaderPool>, waits: &WaitCounter) -> Option<PooledReader> {
+          let started = Instant::now();
+          let permit = self.permits.clone().acquire_owned().await.ok()?;
+          waits.record(started);
+
+          let index = self.idle.lock().unwrap().pop()?;
+
+          return Some(PooledReader {
+               pool: self,
+               index: index,
+               _permit: permit
+          });
+     }
+
+     /// Waits for the running reads and closes the readers, reads waiting for one run on the writer instead.
+     pub(crate) async fn close(&self) -> Result<(), AioError> {
+          let permits = self.permits.acquire_many(self.readers.len() as u32).await
+               .map_err(|_| AioError::ConnectionClosed)?;
+
+          self.permits.close();
+          drop(permits);
+
+          for reader in self.readers.iter() {
+               reader.sqlite_connection.clone().close().await?;
+          }
+
+          return Ok(());
+     }
+}
+
+impl PooledReader {
+     pub(crate) fn reader(&self) -> &Reader {
+          return &self.pool.readers[self.index];
+     }
+}
+
+impl Drop for PooledReader {
+     fn drop(&mut self) {
+          self.pool.idle.lock().unwrap().push(self.index);
+     }
+}
+
+/// How many times a connection was acquired and how long the callers waited for it.
+#[derive(Default)]
+pub(crate) struct WaitCounter {
+     acquisitions: AtomicU64,
+     wait_nanos: AtomicU64,
+     max_wait_nanos: AtomicU64
+}
+
+impl WaitCounter {
+     pub(crate) fn record(&self, started: Instant) {
+          let nanos = started.elapsed().as_nanos().min(u64::MAX as u128) as u64;
+
+          self.acquisitions.fetch_add(1, Ordering::Relaxed);
+          self.wait_nanos.fetch_add(nanos, Ordering::Relaxed);
+          self.max_wait_nanos.fetch_max(nanos, Ordering::Relaxed);
+     }
+
+     /// The acquisitions, the total and the longest wait.
+     pub(crate) fn get(&self) -> (u64, Duration, Duration) {
+          return (
+               self.acquisitions.load(Ordering::Relaxed),
+               Duration::from_nanos(self.wait_nanos.load(Ordering::Relaxed)),
+               Duration::from_nanos(self.max_wait_nanos.load(Ordering::Relaxed))
+          );
+     }
+}
+
+/// The connection a read runs on: a reader of the pool, or the writer, reserved for the call or by the transaction the read runs in.
+pub(crate) enum ReadConnection<'a> {
+     Reader(PooledReader),
+     Writer {
+          connection: &'a AioDatabaseConnection,
+          _guard: Option<OwnedMutexGuard<ConnectionState>>
+     }
+}
+
+impl ReadConnection<'_> {
+     pub(crate) fn sqlite_connection(&self) -> &SqliteConnection {
+          return match self {
+               ReadConnection::Reader(reader) => &reader.reader().sqlite_connection,
+               ReadConnection::Writer { connection, .. } => &connection.sqlite_connection
+          };
+     }
+
+     pub(crate) fn statement_cache(&self) -> &Arc<StatementCache> {
+          return match self {
+               ReadConnection::Reader(reader) => &reader.reader().statement_cache,
+               ReadConnection::Writer { connection, .. } => &connection.statement_cache
+          };
+     }
+}
@@ -1,619 +1,630 @@
-use std::time::Duration;
-
-use bevy_reflect::Struct;
-use hex::encode;
-
-use log::{error, trace};
-
-use tokio_rusqlite::{Connection, Error};
-
-use crate::db::{aio_query::{QueryBuilder, QueryRowResult, QueryRowsResult}, internal::helpers::{get_values_from_generic, query_match_operators}, models::Schema, WalMode};
-use super::{helpers::{set_values_from_row_result, set_values_from_many_rows_result}, schema_gen::{generate_db_schema_query, get_current_schema, get_sql_type}};
-
-static SLEEP_DURATION: Duration = Duration::from_millis(10); //Retry every 10ms
-
-pub(crate) async fn create_table(schema_vec: &Vec<Schema>, name: &str, connection: &Connection) {
-     let create_table_query = generate_db_schema_query(schema_vec, name);
-     let query = create_table_query.clone();
-     _ = connection.call(move |conn| {
-          Ok(conn.execute(&query, []))
-     }).await;
-}
-
-pub(crate) async fn change_db_settings(connection: &Connection) {
-     _ = connection.call(|conn| {
-          conn.pragma_update(None, "journal_size_limit", "-1")?;
-          conn.pragma_update(None, "synchronous", "NORMAL")?;
-          conn.pragma_update(None, "auto_vacuum", "FULL")?;
-          conn.pragma_update(None, "temp_store", "MEMORY")?;
-          conn.pragma_update(None, "journal_mode", "WAL")?;
-          Ok::<(), Error>(())
-     }).await;
-}
-
-pub(crate) async fn set_wal_mode(connection: &Connection, wal_mode: WalMode) -> Result<(), String> {
-     let query = format!("PRAGMA journal_mode={};", wal_mode.to_string());
-     trace!("Executing PRAGMA query: {}", query);
-     
-     let result = connection.call(|conn| {
-          Ok(conn.pragma_update(None, "journal_mode", "WAL"))
-     }).await;
-     
-     if let Ok(_) = result {
-          Ok(())
-     } else {
-          Err(result.unwrap_err().to_string())
-     }
-}
-
-pub(crate) async fn set_wal_mode_to_rollback(connection: &Connection) {
-     _ = connection.call(|conn| {
-          Ok(conn.pragma_update(None, "journal_mode", "DELETE"))
-     }).await;
-}
-
-pub(crate) async fn change_synchronous_settings(connection: &Connection, val: bool) {
-     let sync_value = if !val { "OFF" } else { "NORMAL" };
-     _ = connection.call(move |conn| {
-          Ok(conn.pragma_update(None, "synchronous", sync_value))
-     }).await;
-}
-
-pub(crate) async fn get_current_db_schema(name: &str, connection: &Connection) -> Option<Vec<Schema>> {  
-     let query = format!("SELECT sql FROM sqlite_schema WHERE name = '{}'", name);
-     trace!("Executing schema query: {}", query);
-     
-     let result = connection.call(move |conn| {
-          let mut stmt = conn.prepare(&query)?;
-          let mut rows = stmt.query([])?;
-          
-          if let Some(row) = rows.next()? {
-               let schema_row: String = row.get(0)?;
-               let current_schema = get_current_schema(schema_row);
-               Ok(Some(current_schema))
-          } else {
-               Ok(None)
-          }
-     }).await;
-     
-     match result {
-          Ok(schema) => schema,
-          Err(_) => None
-     }
-}
-
-pub(crate) async fn alter_table_new_column(name: &str, schema: &Schema, connection: &Connection) {
-     let sql_type = get_sql_type(schema.field_type.as_str()).unwrap();
-     let column_name = schema.field_name.as_str();
-
-     let query = format!("ALTER TABLE {name} ADD COLUMN {column_name} {sql_type}");
-     trace!("Executing alter table query: {}", query);
-     
-     _ = connection.call(move |conn| {
-          Ok(conn.execute(&query, []))
-     }).await;
-}
-
-pub(crate) async fn alter_table_drop_column(name: &str, column_name: &str, connection: &Connection) {
-     let query = format!("ALTER TABLE {name} DROP COLUMN {column_name}");
-     trace!("Executing alter table query: {}", query);
-     
-     _ = connection.call(move |conn| {
-          Ok(conn.execute(&query, []))
-     }).await;
-}
-
-pub(crate) async fn insert_value<T:  Default + Struct + Clone>(
-     value: &T, 
-     table_name: &str, 
-     connection: &Connection,
-     time_to_retry: u32, 
-     concurrent: bool) -> 
-     Result<(), ()>
-{
-     let generic_values = get_values_from_generic::<T>(value);
-     let mut query = {
-          if concurrent {
-               format!("BEGIN CONCURRENT; INSERT INTO {} (", table_name)
-          } else {
-               format!("INSERT INTO {} (", table_name)
-          }
-     };
-
-     for generic_value in generic_values.iter().take(generic_values.len() - 1) {
-          query.push_str(generic_value.field_name.as_str());
-          query.push(',');
-     }
-
-     query.push_str(generic_values.iter().last().unwrap().field_name.as_str());
-     query.push(')');
-
-     query.push_str(" VALUES (");
-
-     for generic_value in generic_values.iter().take(generic_values.len() - 1) {
-          if generic_value.field_type == "Vec" {
-               let vec_u8 = generic_value.field_value.try_downcast_ref::<Vec<u8>>().unwrap();
-               let hex = encode(vec_u8);
-               query.push_str(format!("x'{}'", hex).as_str());
-               query.push(',');
-          }
-          else {
-               let mut string_value = format!("{:?}", generic_value.field_value);
-
-               if generic_value.field_type == "String" {
-                    string_value.pop();
-                    string_value.remove(0);
-
-                    if string_value.contains("'") {
-                         string_value = string_value.replace("'", "''");
-                    }
-
-                    string_value = format!("'{}'", string_value);
-               }
-
-               if generic_value.field_type == "bool" {
-                    if string_value.contains("false") {
-                         _ = string_value = 0.to_string();
-                    }
-                    else {
-                         _ = string_value = 1.to_string();
-                    }  
-               }
-
-               query.push_str(string_value.as_str());
-               query.push(',');
-          }
-     }
-
-     let last = generic_values.last().unwrap();
-
-     if last.field_type == "Vec" {
-          let vec_u8 = last.field_value.try_downcast_ref::<Vec<u8>>().unwrap();
-          let hex = encode(vec_u8);
-          query.push_str(format!("x'{}'", hex).as_str());
-          query.push(')');
-     }
-     else {
-          let mut string_value = format!("{:?}", last.field_value);
-
-          if last.field_type == "String" {
-               string_value.pop();
-               string_value.remove(0);
-
-               if string_value.contains("'") {
-                    string_value = string_value.replace("'", "''");
-               }
-
-               string_value = format!("'{}'", string_value);
-          }
-
-          if last.field_type == "bool" {
-               if string_value.contains("false") {
-                    _ = string_value = 0.to_string();
-               }
-               else {
-                    _ = string_value = 1.to_string();
-               }  
-          }
-
-          query.push_str(string_value.as_str());
-          query.push(')');
-     }
-
-     if concurrent {
-          query.push_str("; ");
-          query.push_str("COMMIT;");
-     }
-
-     trace!("Executing insert query: {}", query);
-
-     let mut retries = 0;
-
-     while retries < time_to_retry {
-         let query_clone = query.clone();
-         let function_result = connection.call(move |conn| {
-              Ok(conn.execute(&query_clone, []))
-         }).await.unwrap();
- 
-         if function_result.is_ok() {
-             return Ok(());
-         }
-         else {
-             let error = function_result.unwrap_err();
-             error!("Error occurred on {} retry. Message: {:?}", retries + 1, error);
-             retries = retries + 1;
-         }
-         tokio::time::sleep(SLEEP_DURATION).await;
-     }
- 
-     return Err(());
-}
-
-pub(crate) fn generate_get_query<'a, T:  Default + Struct + Clone>(query_builder: &'a QueryBuilder<'_>) -> String {    
-     let options = &query_builder.query_options;
-     let table_name = &query_builder.table_name;
-     let mut query = format!("SELECT * FROM {table_name}");
-
-     let schema = query_builder.db.get_schema();
-
-     let len = options.len();
-
-     query.push_str(" WHERE ");
-
-     if len > 1 {
-          for option in options.iter().take(options.iter().len() - 1) {
-               let current = schema.iter().find(|x| x.field_name == option.field_name).unwrap();
-               let next = option.next.as_ref().unwrap();
-               let operator = option.operator.as_ref().unwrap();
-               query_match_operators(operator,  &mut query, &option.field_name, &current.field_type, false, Some(next));
-          }
-     } 
-
-     let option = options.iter().last().unwrap();
-
-     let current = schema.iter().find(|x| x.field_name == option.field_name).unwrap();
-     let next = option.next.as_ref().unwrap();
-     let operator = option.operator.as_ref().unwrap();
-     query_match_operators(operator,  &mut query, &option.field_name, &current.field_type, true, Some(next));     
-
-     trace!("Executing get query: {}", query);
-
-     return query;
-}
-
-pub(crate) fn generate_where_query<'a, T:  Default + Struct + Clone>(query_builder: &'a QueryBuilder<'_>) -> String {    
-     let options = &query_builder.query_options;
-     let mut query = format!("WHERE ");
-
-     let schema = query_builder.db.get_schema();
-
-     for option in options.iter().take(options.iter().len() - 1) {
-          let current = schema.iter().find(|x| x.field_name == option.field_name).unwrap();
-          let next = option.next.as_ref().unwrap();
-          let operator = option.operator.as_ref().unwrap();
-          query_match_operators(operator,  &mut query, &option.field_name, &current.field_type, false, Some(next));
-     }
-
-     let option = options.iter().last().unwrap();
-
-     let current = schema.iter().find(|x| x.field_name == option.field_name).unwrap();
-     let next = option.next.as_ref().unwrap();
-     let operator = option.operator.as_ref().unwrap();
-     query_match_operators(operator,  &mut query, &option.field_name, &current.field_type, true, Some(next));
-
-     trace!("Executing where query: {}", query);
-
-     return query;
-}
-
-pub(crate) async fn update_value<T:  Default + Struct + Clone> (
-     value: &T, 
-     table_name: &str, 
-     where_query: &str, 
-     connection: &Connection,
-     time_to_retry: u32, 
-     concurrent: bool) -> 
-     Result<usize, ()> {
-     let generic_values = get_values_from_generic::<T>(value);
-
-     let mut query = {
-          if concurrent {
-               format!("BEGIN CONCURRENT; UPDATE {} SET ", table_name)
-          } else {
-               format!("UPDATE {} SET ", table_name)
-          }
-     };
-
-     for generic_value in generic_values.iter().take(generic_values.len() - 1) {
-          let name = generic_value.field_name.as_str();
-          let value = generic_value.field_value;
-
-          if generic_value.field_type == "Vec" {
-               let vec_u8 = generic_value.field_value.try_downcast_ref::<Vec<u8>>().unwrap();
-               let hex = encode(vec_u8);
-               let set_query = format!("{} = x'{}'", name, hex);
-               query.push_str(set_query.as_str());
-               query.push_str(", ");
-          }
-          else {
-               let mut string_value = format!("{:?}", value);
-
-               if string_value.contains("'") {
-                    string_value = string_value.replace("'", "''");
-               }
-
-               if generic_value.field_type == "bool" {
-                    if string_value.contains("false") {
-                         _ = string_value = 0.to_string();
-                    }
-                    else {
-                         _ = string_value = 1.to_string();
-                    }  
-               }
-
-               let set_query = format!("{} = {}", name, string_value).replace("\"", "'");
-
-               query.push_str(set_query.as_str());
-               query.push_str(", ");
-          }
-     }
-
-     let generic_value = generic_values.iter().last().unwrap();
-
-     let name = generic_value.field_name.as_str();
-     let value = generic_value.field_value;
-
-     if generic_value.field_type == "Vec" {
-          let vec_u8 = generic_value.field_value.try_downcast_ref::<Vec<u8>>().unwrap();
-          let hex = encode(vec_u8);
-          let set_query = format!("{} = x'{}'", name, hex);
-          query.push_str(set_query.as_str());
-          query.push(' ');
-     }
-     else {
-          let mut string_value = format!("{:?}", value);
-               
-          if string_value.contains("'") {
-               string_value = string_value.replace("'", "''");
-          }
-
-          if generic_value.field_type == "bool" {
-               if string_value.contains("false") {
-                    _ = string_value = 0.to_string();
-               }
-               else {
-                    _ = string_value = 1.to_string();
-               }  
-          }
-
-          let set_query = format!("{} = {:?}", name, string_value).replace("\"", "'");
-          query.push_str(set_query.as_str());
-          query.push(' ');     
-     }
-
-     query.push_str(where_query);
-
-     if concurrent {
-          query.push_str("; COMMIT;");
-     }
-
-     trace!("Executing update query: {}", query);
-
-     let mut retries = 0;
-
-     while retries < time_to_retry {
-         let query_clone = query.clone();
-         let function_result = connection.call(move |conn| {
-              Ok(conn.execute(&query_clone, []))
-         }).await.unwrap();
- 
-         if function_result.is_ok() {
-             return Ok(function_result.unwrap());
-         }
-         else {
-             let error = function_result.unwrap_err();
-             error!("Error occurred on {} retry. Message: {:?}", retries + 1, error);
-             retries = retries + 1;
-         }
-         tokio::time::sleep(SLEEP_DURATION).await;
-     }
- 
-     return Err(());
-}
-
-pub(crate) async fn partial_update<T:  Default + Struct + Clone> (
-     field_name: String,
-     field_value: String,
-     table_name: &str, 
-     where_query: &str, 
-     connection: &Connection,
-     time_to_retry: u32,
-     concurrent: bool) -> 
-     Result<usize, ()> {
-
-     let mut query = {
-          if concurrent {
-               format!("BEGIN CONCURRENT; UPDATE {} SET ", table_name)
-          } else {
-               format!("UPDATE {} SET ", table_name)
-          }
-     };
-     
-     let name = field_name;
-     let value = field_value;
-
-     let mut string_value = format!("{:?}", value);
-               
-     if string_value.contains("'") {
-          string_value = string_value.replace("'", "''");
-     }
-     
-     let set_query = format!("{} = {}", name, string_value).replace("\"", "'");
-     query.push_str(set_query.as_str());
-     query.push(' ');
-
-     query.push_str(where_query);
-
-     if concurrent {
-          query.push_str("; COMMIT;");
-     }
-
-     trace!("Executing partial update query: {}", query);
-     
-     let mut retries = 0;
-
-     while retries < time_to_retry {
-         let query_clone = query.clone();
-         let function_result = connection.call(move |conn| {
-              Ok(conn.execute(&query_clone, []))
-         }).await.unwrap();
- 
-         if function_result.is_ok() {
-             return Ok(function_result.unwrap());
-         }
-         else {
-             let error = function_result.unwrap_err();
-             error!("Error occurred on {} retry. Message: {:?}", retries + 1, error);
-             retries = retries + 1;
-         }
-         tokio::time::sleep(SLEEP_DURATION).await;
-     }
- 
-     return Err(());
-}
-
-pub(crate) async fn delete_value<T:  Default + Struct + Clone> (
-     table_name: &str, 
-     where_query: &str, 
-     connection: &Connection,
-     time_to_retry: u32) ->
-     Result<usize, ()> {
-     let mut query = format!("DELETE FROM {} ", table_name);
-     query.push_str(where_query);
-
-     trace!("Executing delete query: {}", query);
-
-     let mut retries = 0;
-
-     while retries < time_to_retry {
-         let query_clone = query.clone();
-         let function_result = connection.call(move |conn| {
-              Ok(conn.execute(&query_clone, []))
-         }).await.unwrap();
- 
-         if function_result.is_ok() {
-             return Ok(function_result.unwrap());
-         }
-         else {
-             let error = function_result.unwrap_err();
-             error!("Error occurred on {} retry. Message: {:?}", retries + 1, error);
-             retries = retries + 1;
-         }
-         tokio::time::sleep(SLEEP_DURATION).await;
-     }
- 
-     return Err(());
-}
-
-pub(crate) async fn any_count_query<T:  Default + Struct + Clone> (
-     table_name: &str, 
-     where_query: &str) -> String {
-     let mut query = format!("SELECT COUNT(*) AS count_total FROM {} ", table_name);
-     query.push_str(where_query);
-
-     trace!("Executing any / count query: {}", query);
-
-     return query;
-}
-
-pub(crate) async fn all_query<T:  Default + Struct + Clone>(
-     table_name: &str) -> String {
-     let query = format!("SELECT COUNT(*) AS count_total FROM {} ", table_name);
-
-     trace!("Executing all query: {}", query);
-
-     return query;
-}
-
-pub(crate) fn get_single_value<'a, T:  Default + Struct + Clone>(query_result: &mut QueryRowResult<T>) {    
-     if let Ok(result) = set_values_from_row_result::<T>(query_result)  {
-          query_result.value = Some(result);
-     } else {
-          query_result.value = None;
-     }
-}
-
-pub(crate) async fn get_many_values<T:  Default + Struct + Clone>(mut query_result: QueryRowsResult<T>) -> Option<Vec<T>>  { 
-     if let Ok(result) = set_values_from_many_rows_result::<T>(&mut query_result)  {
-          Some(result)
-     } else {
-          None
-     }
-}
-
-pub(crate) fn create_unique_index<T:  Default + Struct + Clone> (
-     index_name: &str,
-     table_name: &str, 
-     columns: Vec<String>) -> String {
-     let phantom = T::default();
-     let generic_values = get_values_from_generic::<T>(&phantom);
-     
-     let generic_values_str: Vec<String> = generic_values.iter().map(|x| { let raw = format!("{:?}", *&x.field_name);raw.replace("\"", "") }).collect();
-
-     for column in columns.iter() {
-          if !generic_values_str.contains(column) {
-               panic!("One of the specified columns isn't field of the struct of type T provided.");
-          }
-          else {
-               continue;
-          }
-     }
-
-     drop(generic_values_str);
-     drop(generic_values);
-     drop(phantom);
-
-     let mut query = format!("CREATE UNIQUE INDEX IF NOT EXISTS {} ON {} (", index_name, table_name);
-
-     for column in columns.iter().take(columns.iter().count() - 1) {
-          let column_string = format!("{},", column);
-          query.push_str(&column_string)
-     }
-
-     let last_column = columns.iter().last().unwrap();
-
-     let column_string = format!("{});", last_column);
-     query.push_str(&column_string);
-
-     trace!("Executing create unique index query: {}", query);
-
-     return query;
-}
-
-pub(crate) fn create_index<T:  Default + Struct + Clone> (
-     index_name: &str,
-     table_name: &str, 
-     columns: Vec<String>) -> String {
-     let phantom = T::default();
-     let generic_values = get_values_from_generic::<T>(&phantom);
-     
-     let generic_values_str: Vec<String> = generic_values.iter().map(|x| { let raw = format!("{:?}", *&x.field_name);raw.replace("\"", "") }).collect();
-
-     for column in columns.iter() {
-          if !generic_values_str.contains(column) {
-               panic!("One of the specified columns isn't field of the struct of type T provided.");
-          }
-          else {
-               continue;
-          }
-     }
-
-     drop(generic_values_str);
-     drop(generic_values);
-     drop(phantom);
-
-     let mut query = format!("CREATE INDEX IF NOT EXISTS {} ON {} (", index_name, table_name);
-
-     for column in columns.iter().take(columns.iter().count() - 1) {
-          let column_string = format!("{},", column);
-          query.push_str(&column_string)
-     }
-
-     let last_column = columns.iter().last().unwrap();
-
-     let column_string = format!("{});", last_column);
-     query.push_str(&column_string);
-
-     trace!("Executing create unique index query: {}", query);
-
-     return query;
-}
-
-pub(crate) fn drop_index(
-     index_name: &str) -> String {
-     let query = format!("DROP INDEX IF EXISTS {}", index_name);
-
-     trace!("Executing drop index query: {}", query);
-
-     return query;
+use std::borrow::Borrow;
+use std::future::Future;
+use std::time::Instant;
+
+use bevy_reflect::Struct;
+
+use log::{trace, warn};
+
+use rusqlite::{params_from_iter, types::{FromSql, Value}, OptionalExtension, ToSql};
+use tokio_rusqlite::Connection;
+
+use crate::db::{aio_database::AioDatabaseConnection, aio_error::AioError, aio_options::AioDatabaseOptions, aio_query::QueryBuilder, aio_transaction::OpenSavepoint, internal::helpers::{find_constraint_index, get_values_from_generic, parse_field_value, query_match_operators}, models::{BoundQuery, BulkInsertMode, InsertManyResult, RetryPolicy, Schema, TableDescription, UpsertAction, UpsertResult}, JournalMode};
+use super::statements::{ModelStatements, StatementCache};
+use super::schema_gen::{generate_db_schema_query, generate_field_index_queries, get_column_definition, read_table_description};
+
+pub(crate) async fn create_table(schema_vec: &[Schema], name: &str, connection: &Connection) -> Result<(), AioError> {
+     let query = generate_db_schema_query(schema_vec, name)?;
+     trace!("Executing create table query: {}", query);
+
+     connection.call(move |conn| {
+          conn.execute(&query, [])?;
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+/// Applies the settings of `AioDatabaseOptions`. The ones stored in the file are left as they are for a read only database.
+pub(crate) async fn apply_database_options(connection: &Connection, options: AioDatabaseOptions) -> Result<(), AioError> {
+     connection.call(move |conn| {
+          conn.busy_timeout(options.busy_timeout)?;
+
+          if !options.read_only {
+               // page_size and auto_vacuum only apply before the first table is created, and page_size can't change in WAL mode
+               if let Some(page_size) = options.page_size {
+                    conn.pragma_update(None, "page_size", page_size)?;
+               }
+
+               conn.pragma_update(None, "auto_vacuum", options.auto_vacuum.to_string())?;
+               conn.pragma_update(None, "journal_mode", options.journal_mode.to_string())?;
+          }
+
+          conn.pragma_update(None, "synchronous", options.synchronous.to_string())?;
+          conn.pragma_update(None, "temp_store", options.temp_store.to_string())?;
+
+          if let Some(journal_size_limit) = options.journal_size_limit {
+               conn.pragma_update(None, "journal_size_limit", journal_size_limit)?;
+          }
+
+          if let Some(cache_size) = options.cache_size {
+               conn.pragma_update(None, "cache_size", cache_size)?;
+          }
+
+          if let Some(mmap_size) = options.mmap_size {
+               conn.pragma_update(None, "mmap_size", mmap_size as i64)?;
+          }
+
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+/// Sets `journal_mode` and returns the mode SQLite switched to, which differs when the mode isn't available (e.g. WAL for an in-memory database).
+pub(crate) async fn set_journal_mode(connection: &Connection, journal_mode: JournalMode) -> Result<JournalMode, AioError> {
+     trace!("Executing PRAGMA query: PRAGMA journal_mode={};", journal_mode);
+
+     let journal_mode = connection.call(move |conn| {
+          Ok(conn.pragma_update_and_check(None, "journal_mode", journal_mode.to_string(), |row| row.get::<_, String>(0))?)
+     }).await?;
+
+     return parse_journal_mode(&journal_mode);
+}
+
+/// The `journal_mode` of the connection.
+pub(crate) async fn get_journal_mode(connection: &Connection) -> Result<JournalMode, AioError> {
+     let journal_mode = connection.call(|conn| {
+          Ok(conn.pragma_query_value(None, "journal_mode", |row| row.get::<_, String>(0))?)
+     }).await?;
+
+     return parse_journal_mode(&journal_mode);
+}
+
+fn parse_journal_mode(journal_mode: &str) -> Result<JournalMode, AioError> {
+     return JournalMode::from_pragma(journal_mode)
+          .ok_or_else(|| AioError::Other(format!("Unknown journal mode {}.", journal_mode)));
+}
+
+/// Reads `PRAGMA name` of the connection.
+pub(crate) async fn get_pragma<T: FromSql + Send + 'static>(connection: &Connection, name: &'static str) -> Result<T, AioError> {
+     let value = connection.call(move |conn| {
+          Ok(conn.pragma_query_value(None, name, |row| row.get::<_, T>(0))?)
+     }).await?;
+
+     return Ok(value);
+}
+
+/// Sets `PRAGMA name` of the connection.
+pub(crate) async fn set_pragma<V: ToSql + Send + 'static>(connection: &Connection, name: &'static str, value: V) -> Result<(), AioError> {
+     connection.call(move |conn| {
+          conn.pragma_update(None, name, value)?;
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+pub(crate) async fn change_synchronous_settings(connection: &Connection, val: bool) -> Result<(), AioError> {
+     let sync_value = if !val { "OFF" } else { "NORMAL" };
+     connection.call(move |conn| {
+          conn.pragma_update(None, "synchronous", sync_value)?;
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+pub(crate) async fn get_current_db_schema(name: &str, connection: &Connection) -> Result<Option<Vec<Schema>>, AioError> {  
+     let description = describe_table(name, connection).await?;
+     return Ok(description.map(|x| x.columns));
+}
+
+pub(crate) async fn describe_table(name: &str, connection: &Connection) -> Result<Option<TableDescription>, AioError> {  
+     let name = name.to_string();
+     trace!("Reading schema of table: {}", name);
+     
+     let description = connection.call(move |conn| {
+          Ok(read_table_description(conn, &name)?)
+     }).await?;
+     
+     return Ok(description);
+}
+
+pub(crate) async fn alter_table_new_column(name: &str, schema: &Schema, connection: &Connection) -> Result<(), AioError> {
+     // SQLite can't add PRIMARY KEY columns, the column is added with its type and default only
+     let column_definition = get_column_definition(&Schema { primary_key: false, auto_increment: false, ..schema.clone() })?;
+
+     let query = format!("ALTER TABLE {name} ADD COLUMN {column_definition}");
+     trace!("Executing alter table query: {}", query);
+     
+     connection.call(move |conn| {
+          conn.execute(&query, [])?;
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+pub(crate) async fn create_field_indexes(schema_vec: &[Schema], name: &str, connection: &Connection) -> Result<(), AioError> {
+     let queries = generate_field_index_queries(schema_vec, name);
+
+     if queries.is_empty() {
+          return Ok(());
+     }
+
+     trace!("Executing create field index queries: {:?}", queries);
+
+     connection.call(move |conn| {
+          for query in queries.iter() {
+               conn.execute(query, [])?;
+          }
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+pub(crate) async fn alter_table_rename_column(name: &str, column_name: &str, new_column_name: &str, connection: &Connection) -> Result<(), AioError> {
+     let query = format!("ALTER TABLE {name} RENAME COLUMN {column_name} TO {new_column_name}");
+     trace!("Executing alter table query: {}", query);
+     
+     connection.call(move |conn| {
+          conn.execute(&query, [])?;
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+pub(crate) async fn alter_table_drop_column(name: &str, column_name: &str, connection: &Connection) -> Result<(), AioError> {
+     let query = format!("ALTER TABLE {name} DROP COLUMN {column_name}");
+     trace!("Executing alter table query: {}", query);
+     
+     connection.call(move |conn| {
+          conn.execute(&query, [])?;
+          Ok(())
+     }).await?;
+
+     return Ok(());
+}
+
+/// Executes a mutating statement with its bound parameters, retrying busy and locked errors according to `retry_policy`. Returns the number of rows affected and the last inserted rowid, or the error.
+pub(crate) async fn execute_with_retries(
+     query: String,
+     params: Vec<Value>,
+     table_name: &str,
+     connection: &AioDatabaseConnection,
+     retry_policy: &RetryPolicy) ->
+     Result<(usize, i64), AioError> {
+     return with_retries(retry_policy, || {
+          let query = query.clone();
+          let params = params.clone();
+          let table_name = table_name.to_string();
+          let statement_cache = connection.statement_cache.clone();
+
+          connection.sqlite_connection.call(move |conn| {
+               let result = execute_bound(conn, &statement_cache, &query, params)
+                    .map(|rows| (rows, conn.last_insert_rowid()));
+               Ok(result.map_err(|error| to_aio_error(conn, &table_name, error)))
+          })
+     }).await;
+}
+
+/// Runs `attempt` again while it fails with a busy or locked error, waiting between the attempts according to `retry_policy`.
+/// Every other error, and the last busy one, is returned.
+async fn with_retries<R, F, Fut>(retry_policy: &RetryPolicy, mut attempt: F) -> Result<R, AioError>
+     where F: FnMut() -> Fut, Fut: Future<Output = Result<Result<R, AioError>, tokio_rusqlite::Error>> {
+     let started = Instant::now();
+     let mut attempts = 0;
+
+     loop {
+          attempts += 1;
+
+          let error = match attempt().await? {
+               Ok(result) => return Ok(result),
+               Err(error) if error.is_busy() && attempts < retry_policy.max_attempts => error,
+               Err(error) => return Err(error)
+          };
+
+          let wait = retry_policy.wait_after(attempts);
+
+          if retry_policy.deadline.is_some_and(|deadline| started.elapsed() + wait > deadline) {
+               return Err(error);
+          }
+
+          warn!("Attempt {} failed, retrying in {:?}. Message: {:?}", attempts, wait, error);
+          tokio::time::sleep(wait).await;
+     }
+}
+
+/// Converts the error of a statement on `table_name`, resolving the index of a violated constraint.
+fn to_aio_error(conn: &rusqlite::Connection, table_name: &str, error: rusqlite::Error) -> AioError {
+     let mut error = AioError::from(error);
+     if let AioError::ConstraintViolation { constraint, index_name, .. } = &mut error {
+          *index_name = find_constraint_index(conn, table_name, constraint);
+     }
+
+     return error;
+}
+
+fn execute_bound(
+     conn: &mut rusqlite::Connection, 
+     statement_cache: &StatementCache, 
+     query: &str, 
+     params: Vec<Value>) -> Result<usize, rusqlite::Error> {
+     return statement_cache.prepare(conn, query)?.execute(params_from_iter(params));
+}
+
+pub(crate) async fn insert_value<T:  Default + Struct + Clone>(
+     value: &T, 
+     statements: &ModelStatements, 
+     connection: &AioDatabaseConnection,
+     retry_policy: &RetryPolicy) -> 
+     Result<i64, AioError>
+{
+     let generic_values = statements.to_columns(get_values_from_generic::<T>(value));
+     let query = statements.insert_query_for(&generic_values).into_owned();
+     let params = statements.insert_params(&generic_values)?;
+
+     trace!("Executing insert query: {}", query);
+
+     let (_, rowid) = execute_with_retries(query, params, &statements.table_name, connection, retry_policy).await?;
+
+     return Ok(rowid);
+}
+
+/// Inserts the value or resolves the conflict on `conflict_fields` with `action`, in a savepoint so finding the conflicting row and writing are atomic.
+pub(crate) async fn upsert_value<T: Default + Struct + Clone>(
+     value: &T,
+     statements: &ModelStatements,
+     conflict_fields: Vec<String>,
+     action: UpsertAction,
+     connection: &AioDatabaseConnection,
+     retry_policy: &RetryPolicy) ->
+     Result<UpsertResult, AioError>
+{
+     if conflict_fields.is_empty() {
+          return Err(AioError::SchemaMismatch("No conflict fields were provided for the upsert.".into()));
+     }
+
+     let to_column = |field: &String| statements.column_name(field).map(String::from)
+          .ok_or_else(|| AioError::SchemaMismatch(format!("{} isn't a column of table {}.", field, statements.table_name)));
+
+     let conflict_columns = conflict_fields.iter().map(to_column).collect::<Result<Vec<String>, AioError>>()?;
+     let action = match action {
+          UpsertAction::Update(fields) => UpsertAction::Update(fields.iter().map(to_column).collect::<Result<Vec<String>, AioError>>()?),
+          action => action
+     };
+
+     let generic_values = statements.to_columns(get_values_from_generic::<T>(value));
+     let (query, params, select_query, select_params) = statements.upsert_params(&generic_values, &conflict_columns, &action)?;
+
+     trace!("Executing upsert query: {}", query);
+
+     return with_retries(retry_policy, || {
+          let query = query.clone();
+          let params = params.clone();
+          let select_query = select_query.clone();
+          let select_params = select_params.clone();
+          let table_name = statements.table_name.clone();
+          let statement_cache = connection.statement_cache.clone();
+
+          connection.sqlite_connection.call(move |conn| {
+               conn.execute_batch("SAVEPOINT aio_upsert")?;
+
+               let result = statement_cache.prepare(conn, &select_query)
+                    .and_then(|mut stmt| stmt.query_row(params_from_iter(select_params), |row| row.get::<_, i64>(0)).optional())
+                    .and_then(|existing| {
+                         let rows = statement_cache.prepare(conn, &query)?.execute(params_from_iter(params))?;
+
+                         Ok(match existing {
+                              Some(rowid) if rows > 0 => UpsertResult::Updated(rowid),
+                              Some(rowid) => UpsertResult::Unchanged(rowid),
+                              None => UpsertResult::Inserted(conn.last_insert_rowid())
+                         })
+                    });
+
+               match result {
+                    Ok(upserted) => {
+                         conn.execute_batch("RELEASE aio_upsert")?;
+                         Ok(Ok(upserted))
+                    },
+                    Err(error) => {
+                         // The savepoint is released even if rolling back to it fails, and the original error is returned.
+                         let _ = conn.execute_batch("ROLLBACK TO aio_upsert");
+                         let _ = conn.execute_batch("RELEASE aio_upsert");
+                         Ok(Err(to_aio_error(conn, &table_name, error)))
+                    }
+               }
+          })
+     }).await;
+}
+
+/// Rows sent to the connection thread per call by `insert_many`.
+const INSERT_MANY_CHUNK_SIZE: usize = 1000;
+
+/// Inserts the values in chunks of `INSERT_MANY_CHUNK_SIZE` rows reusing one prepared statement, so the iterator is never collected whole.
+/// The chunks run inside `savepoint`, so the batch is atomic both on its own and inside a transaction. A chunk that fails with a busy or locked
+/// error is rolled back to its own savepoint and retried according to `retry_policy`, as is releasing `savepoint`.
+pub(crate) async fn insert_many<T: Default + Struct + Clone, B: Borrow<T>>(
+     values: impl IntoIterator<Item = B>, 
+     statements: &ModelStatements, 
+     connection: &AioDatabaseConnection,
+     mode: BulkInsertMode,
+     savepoint: &mut OpenSavepoint<'_>,
+     retry_policy: &RetryPolicy) -> 
+     Result<InsertManyResult, AioError>
+{
+     let mut values = values.into_iter();
+     let query = statements.insert_query_for(&statements.to_columns(get_values_from_generic::<T>(&T::default()))).into_owned();
+     let savepoint_name = savepoint.name();
+     let mut result = InsertManyResult::default();
+
+     trace!("Executing bulk insert query: {}", query);
+
+     connection.sqlite_connection.call(move |conn| Ok(conn.execute_batch(&format!("SAVEPOINT {}", savepoint_name))?)).await?;
+
+     loop {
+          let chunk = values.by_ref()
+               .take(INSERT_MANY_CHUNK_SIZE)
+               .map(|value| statements.insert_params(&statements.to_columns(get_values_from_generic::<T>(value.borrow()))))
+               .collect::<Result<Vec<Vec<Value>>, AioError>>();
+
+          let chunk = match chunk {
+               Ok(chunk) if chunk.is_empty() => break,
+               Ok(chunk) => chunk,
+               Err(error) => {
+                    rollback_savepoint(connection, savepoint).await?;
+                    return Err(error);
+               }
+          };
+
+          let offset = result.inserted + result.failed.len();
+
+          let chunk_result = with_retries(retry_policy, || {
+               let query = query.clone();
+               let chunk = chunk.clone();
+               let table_name = statements.table_name.clone();
+               let statement_cache = connection.statement_cache.clone();
+
+               connection.sqlite_connection.call(move |conn| {
+                    let mut chunk_result = InsertManyResult::default();
+                    conn.execute_batch("SAVEPOINT aio_insert_chunk")?;
+
+                    for (index, params) in chunk.into_iter().enumerate() {
+                         let row_result = statement_cache.prepare(conn, &query)
+                              .and_then(|mut stmt| stmt.execute(params_from_iter(params)))
+                              .map_err(|error| to_aio_error(conn, &table_name, error));
+
+                         match row_result {
+                              Ok(_) => chunk_result.inserted += 1,
+                              Err(error) if mode == BulkInsertMode::AllOrNothing || error.is_busy() => {
+                                   conn.execute_batch("ROLLBACK TO aio_insert_chunk; RELEASE aio_insert_chunk")?;
+                                   return Ok(Err(error));
+                              },
+                              Err(error) => chunk_result.failed.push((offset + index, error))
+                         }
+                    }
+
+                    conn.execute_batch("RELEASE aio_insert_chunk")?;
+                    Ok(Ok(chunk_result))
+               })
+          }).await;
+
+          match chunk_result {
+               Ok(chunk_result) => {
+                    result.inserted += chunk_result.inserted;
+                    result.failed.extend(chunk_result.failed);
+               },
+               Err(error) => {
+                    rollback_savepoint(connection, savepoint).await?;
+                    return Err(error);
+               }
+          }
+     }
+
+     with_retries(retry_policy, || connection.sqlite_connection.call(move |conn| {
+          Ok(conn.execute_batch(&format!("RELEASE {}", savepoint_name)).map_err(AioError::from))
+     })).await?;
+     savepoint.close();
+
+     return Ok(result);
+}
+
+async fn rollback_savepoint(connection: &AioDatabaseConnection, savepoint: &mut OpenSavepoint<'_>) -> Result<(), AioError> {
+     let name = savepoint.name();
+     connection.sqlite_connection.call(move |conn| Ok(conn.execute_batch(&format!("ROLLBACK TO {}; RELEASE {}", name, name))?)).await?;
+     savepoint.close();
+
+     return Ok(());
+}
+
+pub(crate) fn generate_get_query<T:  Default + Struct + Clone>(query_builder: &QueryBuilder<'_>) -> Result<BoundQuery, AioError> {    
+     let table_name = &query_builder.db.model::<T>()?.name;
+     let where_query = generate_where_query::<T>(query_builder)?;
+
+     let query = format!("SELECT * FROM {table_name} {}", where_query.query);
+
+     trace!("Executing get query: {}", query);
+
+     return Ok(BoundQuery { query, params: where_query.params });
+}
+
+pub(crate) fn generate_where_query<T:  Default + Struct + Clone>(query_builder: &QueryBuilder<'_>) -> Result<BoundQuery, AioError> {    
+     let options = &query_builder.query_options;
+     let mut query = "WHERE ".to_string();
+     let mut params = Vec::with_capacity(options.len());
+
+     let table = query_builder.db.model::<T>()?;
+     let schema = &table.schema;
+     let statements = &table.statements;
+
+     let option = options.iter().last()
+          .ok_or_else(|| AioError::SchemaMismatch("No query filter was provided.".into()))?;
+
+     for option in options.iter().take(options.iter().len() - 1) {
+          let current = find_schema_field(schema, statements.column_name(&option.field_name).unwrap_or(&option.field_name))?;
+          let next = option.next.as_ref().unwrap();
+          let operator = option.operator.as_ref().unwrap();
+          query_match_operators(operator,  &mut query, &mut params, &current.field_name, &current.field_type, false, Some(next));
+     }
+
+     let current = find_schema_field(schema, statements.column_name(&option.field_name).unwrap_or(&option.field_name))?;
+     let next = option.next.as_ref().unwrap();
+     let operator = option.operator.as_ref().unwrap();
+     query_match_operators(operator,  &mut query, &mut params, &current.field_name, &current.field_type, true, Some(next));
+
+     trace!("Executing where query: {}", query);
+
+     return Ok(BoundQuery { query, params });
+}
+
+pub(crate) fn find_schema_field<'a>(schema: &'a [Schema], field_name: &str) -> Result<&'a Schema, AioError> {
+     return schema.iter()
+          .find(|x| x.field_name == field_name)
+          .ok_or_else(|| AioError::SchemaMismatch(format!("{} isn't a field of the struct of type T provided.", field_name)));
+}
+
+pub(crate) async fn update_value<T:  Default + Struct + Clone> (
+     value: &T, 
+     statements: &ModelStatements, 
+     where_query: BoundQuery, 
+     connection: &AioDatabaseConnection,
+     retry_policy: &RetryPolicy) -> 
+     Result<usize, AioError> {
+     let table_name = statements.table_name.as_str();
+     let generic_values = statements.to_columns(get_values_from_generic::<T>(value));
+
+     let (update_query, mut params) = statements.update_params(&generic_values)?;
+     let query = format!("{} {}", update_query, where_query.query);
+     params.extend(where_query.params);
+
+     trace!("Executing update query: {}", query);
+
+     let (rows, _) = execute_with_retries(query, params, table_name, connection, retry_policy).await?;
+
+     return Ok(rows);
+}
+
+pub(crate) async fn partial_update(
+     field: &Schema,
+     field_value: Option<String>,
+     table_name: &str, 
+     where_query: BoundQuery, 
+     connection: &AioDatabaseConnection,
+     retry_policy: &RetryPolicy) -> 
+     Result<usize, AioError> {
+     let query = format!("UPDATE {} SET {} = ? {}", table_name, field.field_name, where_query.query);
+
+     let mut params = vec![parse_field_value(field_value.as_deref(), field)?];
+     params.extend(where_query.params);
+
+     trace!("Executing partial update query: {}", query);
+     
+     let (rows, _) = execute_with_retries(query, params, table_name, connection, retry_policy).await?;
+
+     return Ok(rows);
+}
+
+pub(crate) async fn delete_value(
+     table_name: &str, 
+     where_query: BoundQuery, 
+     connection: &AioDatabaseConnection,
+     retry_policy: &RetryPolicy) ->
+     Result<usize, AioError> {
+     let query = format!("DELETE FROM {} {}", table_name, where_query.query);
+
+     trace!("Executing delete query: {}", query);
+
+     let (rows, _) = execute_with_retries(query, where_query.params, table_name, connection, retry_policy).await?;
+
+     return Ok(rows);
+}
+
+pub(crate) async fn update_by_id<T:  Default + Struct + Clone> (
+     id: Value,
+     value: &T, 
+     statements: &ModelStatements, 
+     connection: &AioDatabaseConnection,
+     retry_policy: &RetryPolicy) -> 
+     Result<usize, AioError> {
+     let generic_values = statements.to_columns(get_values_from_generic::<T>(value));
+     let params = statements.update_by_id_params(&generic_values, id)?;
+
+     trace!("Executing update by id query: {}", statements.update_by_id_query);
+
+     let (rows, _) = execute_with_retries(statements.update_by_id_query.clone(), params, &statements.table_name, connection, retry_policy).await?;
+
+     return Ok(rows);
+}
+
+pub(crate) async fn delete_by_id(
+     id: Value,
+     statements: &ModelStatements, 
+     connection: &AioDatabaseConnection,
+     retry_policy: &RetryPolicy) -> 
+     Result<usize, AioError> {
+     trace!("Executing delete by id query: {}", statements.delete_by_id_query);
+
+     let (rows, _) = execute_with_retries(statements.delete_by_id_query.clone(), vec![id], &statements.table_name, connection, retry_policy).await?;
+
+     return Ok(rows);
+}
+
+pub(crate) async fn any_count_query(
+     table_name: &str, 
+     where_query: BoundQuery) -> BoundQuery {
+     let query = format!("SELECT COUNT(*) AS count_total FROM {} {}", table_name, where_query.query);
+
+     trace!("Executing any / count query: {}", query);
+
+     return BoundQuery { query, params: where_query.params };
+}
+
+pub(crate) async fn all_query(
+     table_name: &str) -> BoundQuery {
+     let query = format!("SELECT COUNT(*) AS count_total FROM {} ", table_name);
+
+     trace!("Executing all query: {}", query);
+
+     return BoundQuery { query, params: Vec::new() };
+}
+
+/// Returns the CREATE INDEX (or CREATE UNIQUE INDEX) statement for the fields `columns` of **T**, mapped to their columns.
+pub(crate) fn create_index<T: Default + Struct + Clone> (
+     index_name: &str,
+     statements: &ModelStatements,
+     columns: Vec<String>,
+     unique: bool) -> Result<String, AioError> {
+     if columns.is_empty() {
+          return Err(AioError::SchemaMismatch("No columns were provided for the index.".into()));
+     }
+
+     let phantom = T::default();
+     let columns = columns.iter()
+          .map(|x| {
+               if phantom.field(x).is_none() {
+                    return Err(AioError::SchemaMismatch(format!("{} isn't a field of the struct of type T provided.", x)));
+               }
+
+               statements.column_name(x).map(String::from).ok_or_else(|| AioError::SchemaMismatch(format!("{} is skipped and can't be indexed.", x)))
+          })
+          .collect::<Result<Vec<String>, AioError>>()?;
+
+     let index_type = if unique { "UNIQUE INDEX" } else { "INDEX" };
+     let query = format!("CREATE {} IF NOT EXISTS {} ON {} ({});", index_type, index_name, statements.table_name, columns.join(","));
+
+     trace!("Executing create {} query: {}", index_type.to_lowercase(), query);
+
+     return Ok(query);
+}
+
+pub(crate) fn drop_index(
+     index_name: &str) -> String {
+     let query = format!("DROP INDEX IF EXISTS {}", index_name);
+
+     trace!("Executing drop index query: {}", query);
+
+     return query;
 }
\ No newline at end of file
@@ -1,120 +1,272 @@
-use crate::db::models::Schema;
-
-pub(crate) fn get_sql_type(rust_type: &str) -> Option<String> {
-
-     // "bool" => return Some("NUMERIC".into()),
-     // "u8" => return Some("INTEGER".into()),
-     // "u16" => return Some("INTEGER".into()),
-     // "u32" => return Some("INTEGER".into()),
-     // "u64" => return Some("INTEGER".into()),
-     // "i8" => return Some("INTEGER".into()),
-     // "i16" => return Some("INTEGER".into()),
-     // "i32" => return Some("INTEGER".into()),
-     // "i64" => return Some("INTEGER".into()),
-     // "f32" => return Some("REAL".into()),
-     // "f64" => return Some("REAL".into()),
-     // "char" => return Some("TEXT".into()),
-     // "String" => return Some("TEXT".into()),
-     // "Vec" => return Some("BLOB".into()),
-     // _ => return None
-
-     // "bool" => return Some("NUMERIC".into()),
-     // "u8" => return Some("INTEGER".into()),
-     // "u16" => return Some("INTEGER".into()),
-     // "u32" => return Some("BIGINT".into()),
-     // "u64" => return Some("UNSIGNED BIG INT".into()),
-     // "i8" => return Some("TINYINT".into()),
-     // "i16" => return Some("SMALLINT".into()),
-     // "i32" => return Some("INTEGER".into()),
-     // "i64" => return Some("BIGINT".into()),
-     // "f32" => return Some("REAL".into()),
-     // "f64" => return Some("REAL".into()),
-     // "char" => return Some("TEXT".into()),
-     // "String" => return Some("TEXT".into()),
-     // "Vec" => return Some("BLOB".into()),
-
-     match rust_type {
-          "bool" => return Some("NUMERIC".into()),
-          "u8" => return Some("INTEGER".into()),
-          "u16" => return Some("INTEGER".into()),
-          "u32" => return Some("BIGINT".into()),
-          "u64" => return Some("UNSIGNED BIG INT".into()),
-          "i8" => return Some("TINYINT".into()),
-          "i16" => return Some("SMALLINT".into()),
-          "i32" => return Some("INTEGER".into()),
-          "i64" => return Some("BIGINT".into()),
-          "f32" => return Some("REAL".into()),
-          "f64" => return Some("REAL".into()),
-          "char" => return Some("TEXT".into()),
-          "String" => return Some("TEXT".into()),
-          "Vec" => return Some("BLOB".into()),
-          _ => return None
-     }
-}
-
-pub(crate) fn get_current_schema(query_result: String) -> Vec<Schema> {
-     let content = extract_parentheses_contents(&query_result).unwrap();
-     let list = split_by_comma(&content);
-     let schema = split_by_empty_space(list);
-     return schema;
-}
-
-pub(crate) fn extract_parentheses_contents(input: &str) -> Option<String> {
-     let mut result = String::new();
-     let mut in_parentheses = false;
- 
-     for c in input.chars() {
-         match c {
-             '(' => {
-                 in_parentheses = true;
-             }
-             ')' => {
-                 break; // Stop when encountering the closing parenthesis
-             }
-             _ => {
-                 if in_parentheses {
-                     result.push(c);
-                 }
-             }
-         }
-     }
- 
-     if !result.is_empty() && in_parentheses {
-         Some(result)
-     } else {
-         None
-     }
-}
-
-pub(crate) fn split_by_comma(input: &str) -> Vec<&str> {
-     return input.split(',').map(|s| s.trim()).collect();
-}
-
-pub(crate) fn split_by_empty_space(input: Vec<&str>) -> Vec<Schema> {
-     let mut list: Vec<Schema> = Vec::new();
-
-     for field in input {
-          let splitted: Vec<&str> = field.split(' ').map(|f| f.trim()).collect();
-          list.push(Schema {
-               field_name: splitted.first().unwrap().to_string(),
-               field_type: splitted.last().unwrap().to_string()
-          });
-     }
-
-     return list;
-}
-
-pub(crate) fn generate_db_schema_query(schema_vec: &Vec<Schema>, name: &str) -> String {
-     let mut create_table = format!("CREATE TABLE IF NOT EXISTS {} (", name);
-               
-     for new_field in schema_vec.iter().take(schema_vec.len() - 1) {
-          let sql_type = get_sql_type(new_field.field_type.as_str()).unwrap();
-          create_table = format!("{} {},", create_table, format!("{} {}", new_field.field_name, sql_type));
-     }
-
-     let last_field_type = schema_vec.iter().last().unwrap();
-     let sql_type = get_sql_type(last_field_type.field_type.as_str()).unwrap();
-     create_table = format!("{} {})", create_table, format!("{} {}", last_field_type.field_name, sql_type));
-
-     return create_table;
-}
\ No newline at end of file
+use rusqlite::OptionalExtension;
+use rusqlite::types::Value;
+
+use crate::db::{aio_error::AioError, models::{ForeignKeyInfo, IndexInfo, Schema, TableDescription}};
+
+pub(crate) fn get_sql_type(rust_type: &str) -> Option<String> {
+     match rust_type {
+          "bool" => return Some("NUMERIC".into()),
+          "u8" => return Some("INTEGER".into()),
+          "u16" => return Some("INTEGER".into()),
+          "u32" => return Some("BIGINT".into()),
+          "u64" => return Some("UNSIGNED BIG INT".into()),
+          "i8" => return Some("TINYINT".into()),
+          "i16" => return Some("SMALLINT".into()),
+          "i32" => return Some("INTEGER".into()),
+          "i64" => return Some("BIGINT".into()),
+          "f32" => return Some("REAL".into()),
+          "f64" => return Some("REAL".into()),
+          "char" => return Some("TEXT".into()),
+          "String" => return Some("TEXT".into()),
+          "Vec" => return Some("BLOB".into()),
+          _ => return None
+     }
+}
+
+/// Reads the live schema of a table, None if the table doesn't exist.
+pub(crate) fn read_table_description(conn: &rusqlite::Connection, name: &str) -> Result<Option<TableDescription>, rusqlite::Error> {
+     let table_sql: Option<String> = conn
+          .query_row("SELECT sql FROM sqlite_schema WHERE type = 'table' AND name = ?1", [name], |row| row.get(0))
+          .optional()?;
+
+     let Some(table_sql) = table_sql else {
+          return Ok(None);
+     };
+
+     let has_auto_increment = declares_auto_increment(&table_sql);
+
+     let indexes = read_indexes(conn, name)?;
+
+     let mut stmt = conn.prepare("SELECT name, type, \"notnull\", dflt_value, pk, hidden FROM pragma_table_xinfo(?1) ORDER BY cid")?;
+     let columns = stmt
+          .query_map([name], |row| {
+               let field_name: String = row.get(0)?;
+               let field_type: String = row.get(1)?;
+               let primary_key = row.get::<_, i64>(4)? > 0;
+               let hidden: i64 = row.get(5)?;
+
+               let single_column_index = |unique: bool| indexes.iter()
+                    .any(|x| x.unique == unique && x.origin != "pk" && x.columns.len() == 1 && x.columns[0] == field_name);
+
+               Ok(Schema {
+                    unique: single_column_index(true),
+                    index: single_column_index(false),
+                    auto_increment: primary_key && has_auto_increment && field_type.eq_ignore_ascii_case("INTEGER"),
+                    field_name: field_name,
+                    field_type: field_type,
+                    nullable: !row.get::<_, bool>(2)?,
+                    primary_key: primary_key,
+                    default: row.get(3)?,
+                    renamed_from: Vec::new(),
+                    hidden: hidden == 1,
+                    generated: hidden == 2 || hidden == 3
+               })
+          })?
+          .collect::<Result<Vec<Schema>, rusqlite::Error>>()?;
+
+     let mut stmt = conn.prepare("SELECT \"from\", \"table\", \"to\", on_update, on_delete FROM pragma_foreign_key_list(?1) ORDER BY id, seq")?;
+     let foreign_keys = stmt
+          .query_map([name], |row| {
+               Ok(ForeignKeyInfo {
+                    column_name: row.get(0)?,
+                    foreign_table: row.get(1)?,
+                    foreign_column: row.get(2)?,
+                    on_update: row.get(3)?,
+                    on_delete: row.get(4)?
+               })
+          })?
+          .collect::<Result<Vec<ForeignKeyInfo>, rusqlite::Error>>()?;
+
+     return Ok(Some(TableDescription {
+          table_name: name.to_string(),
+          columns: columns,
+          indexes: indexes,
+          foreign_keys: foreign_keys
+     }));
+}
+
+/// True if the CREATE TABLE statement has a `PRIMARY KEY [ASC | DESC] [ON CONFLICT ...] AUTOINCREMENT` constraint, the only place SQLite allows the keyword.
+/// SQLite keeps no other record of it (`sqlite_sequence` only has a row once a row was inserted), so the statement is tokenized:
+/// literals, quoted names and comments are skipped and a default or column name containing the word doesn't count.
+fn declares_auto_increment(table_sql: &str) -> bool {
+     let tokens = sql_tokens(table_sql);
+
+     return tokens.windows(2).enumerate()
+          .filter(|(_, x)| x[0] == "PRIMARY" && x[1] == "KEY")
+          .any(|(index, _)| {
+               let mut next = index + 2;
+
+               if tokens.get(next).is_some_and(|x| x == "ASC" || x == "DESC") {
+                    next += 1;
+               }
+
+               if tokens.get(next).is_some_and(|x| x == "ON") && tokens.get(next + 1).is_some_and(|x| x == "CONFLICT") {
+                    next += 3;
+               }
+
+               return tokens.get(next).is_some_and(|x| x == "AUTOINCREMENT");
+          });
+}
+
+/// Splits a statement into uppercase words and punctuation. Literals and quoted names become a single `?`, comments are left out.
+fn sql_tokens(sql: &str) -> Vec<String> {
+     let mut tokens = Vec::new();
+     let mut chars = sql.chars().peekable();
+
+     while let Some(c) = chars.next() {
+          match c {
+               '\'' | '"' | '`' | '[' => {
+                    let end = if c == '[' { ']' } else { c };
+
+                    // A doubled quote is an escaped one
+                    while let Some(x) = chars.next() {
+                         if x == end && (end == ']' || chars.next_if_eq(&end).is_none()) {
+                              break;
+                         }
+                    }
+
+                    tokens.push("?".to_string());
+               },
+               '-' if chars.peek() == Some(&'-') => {
+                    for x in chars.by_ref() {
+                         if x == '\n' {
+                              break;
+                         }
+                    }
+               },
+               '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    let mut previous = ' ';
+
+                    for x in chars.by_ref() {
+                         if previous == '*' && x == '/' {
+                              break;
+                         }
+                         previous = x;
+                    }
+               },
+               c if c.is_alphanumeric() || c == '_' => {
+                    let mut word = c.to_uppercase().to_string();
+
+                    while let Some(x) = chars.next_if(|x| x.is_alphanumeric() || *x == '_') {
+                         word.extend(x.to_uppercase());
+                    }
+
+                    tokens.push(word);
+               },
+               c if c.is_whitespace() => { },
+               c => tokens.push(c.to_string())
+          }
+     }
+
+     return tokens;
+}
+
+fn read_indexes(conn: &rusqlite::Connection, name: &str) -> Result<Vec<IndexInfo>, rusqlite::Error> {
+     let mut stmt = conn.prepare("SELECT name, \"unique\", origin FROM pragma_index_list(?1) ORDER BY seq")?;
+     let mut indexes = stmt
+          .query_map([name], |row| {
+               Ok(IndexInfo {
+                    name: row.get(0)?,
+                    columns: Vec::new(),
+                    unique: row.get(1)?,
+                    origin: row.get(2)?
+               })
+          })?
+          .collect::<Result<Vec<IndexInfo>, rusqlite::Error>>()?;
+
+     let mut stmt = conn.prepare("SELECT name FROM pragma_index_info(?1) ORDER BY seqno")?;
+
+     for index in indexes.iter_mut() {
+          index.columns = stmt
+               .query_map([&index.name], |row| row.get::<_, Option<String>>(0))?
+               .filter_map(|x| x.transpose())
+               .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+     }
+
+     return Ok(indexes);
+}
+
+/// Returns the primary key field of the model, if any. Composite keys and auto increment keys of non integer types are rejected.
+pub(crate) fn get_primary_key(schema_vec: &[Schema]) -> Result<Option<&Schema>, AioError> {
+     let mut primary_keys = schema_vec.iter().filter(|x| x.primary_key);
+     let primary_key = primary_keys.next();
+
+     if primary_keys.next().is_some() {
+          return Err(AioError::SchemaMismatch("Only one field can be marked as the primary key.".into()));
+     }
+
+     if let Some(primary_key) = primary_key
+          && primary_key.auto_increment && !matches!(primary_key.field_type.as_str(), "u8" | "u16" | "u32" | "i8" | "i16" | "i32" | "i64") {
+          return Err(AioError::SchemaMismatch(format!("{} is of type {}, auto increment keys must be integers (up to i64).", primary_key.field_name, primary_key.field_type)));
+     }
+
+     return Ok(primary_key);
+}
+
+pub(crate) fn get_column_definition(field: &Schema) -> Result<String, AioError> {
+     if field.auto_increment {
+          return Ok(format!("{} INTEGER PRIMARY KEY AUTOINCREMENT", field.field_name));
+     }
+
+     let sql_type = get_sql_type(field.field_type.as_str())
+          .ok_or_else(|| AioError::UnsupportedFieldType(field.field_type.clone()))?;
+
+     let mut definition = format!("{} {}", field.field_name, sql_type);
+
+     if field.primary_key {
+          definition.push_str(" PRIMARY KEY");
+     }
+
+     if let Some(default) = &field.default {
+          definition.push_str(&format!(" DEFAULT {}", default));
+     }
+
+     return Ok(definition);
+}
+
+/// Converts a value to a SQL literal usable as a column `DEFAULT`, e.g. `it's` to `'it''s'`. None for NULL and values without a literal.
+pub(crate) fn get_sql_literal(value: &Value) -> Option<String> {
+     return match value {
+          Value::Null => None,
+          Value::Integer(value) => Some(value.to_string()),
+          Value::Real(value) if value.is_finite() => Some(format!("{:?}", value)),
+          Value::Real(_) => None,
+          Value::Text(value) if value.contains('\0') => None,
+          Value::Text(value) => Some(format!("'{}'", value.replace('\'', "''"))),
+          Value::Blob(value) => Some(format!("X'{}'", value.iter().map(|x| format!("{:02X}", x)).collect::<String>()))
+     };
+}
+
+/// Returns the CREATE INDEX statements for the fields marked with `#[aio(unique)]` or `#[aio(index)]`.
+pub(crate) fn generate_field_index_queries(schema_vec: &[Schema], name: &str) -> Vec<String> {
+     let mut queries = Vec::new();
+
+     for field in schema_vec.iter() {
+          if field.unique {
+               queries.push(format!("CREATE UNIQUE INDEX IF NOT EXISTS {name}_{0}_unique ON {name} ({0})", field.field_name));
+          }
+
+          if field.index {
+               queries.push(format!("CREATE INDEX IF NOT EXISTS {name}_{0}_index ON {name} ({0})", field.field_name));
+          }
+     }
+
+     return queries;
+}
+
+pub(crate) fn generate_db_schema_query(schema_vec: &[Schema], name: &str) -> Result<String, AioError> {
+     if schema_vec.is_empty() {
+          return Err(AioError::SchemaMismatch(format!("The struct for table {} has no fields.", name)));
+     }
+
+     get_primary_key(schema_vec)?;
+
+     let columns = schema_vec.iter()
+          .map(get_column_definition)
+          .collect::<Result<Vec<String>, AioError>>()?;
+
+     let create_table = format!("CREATE TABLE IF NOT EXISTS {} ({})", name, columns.join(", "));
+
+     return Ok(create_table);
+}
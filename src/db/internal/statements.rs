@@ -0,0 +1,253 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rusqlite::CachedStatement;
+use rusqlite::types::Value;
+
+use crate::db::aio_error::AioError;
+use crate::db::models::{GenericValue, ModelInfo, Schema, StatementCacheStats, UpsertAction};
+use super::helpers::get_sql_value;
+use super::schema_gen::get_primary_key;
+
+/// Default capacity of the prepared statement cache, the same as rusqlite's default.
+pub(crate) const DEFAULT_STATEMENT_CACHE_CAPACITY: usize = 16;
+
+/// Wraps rusqlite's `prepare_cached` and counts cache hits and misses.
+///
+/// rusqlite doesn't expose whether a statement came from its LRU cache, so the SQL texts are mirrored here with the same capacity and eviction order.
+/// The counts are an approximation: rusqlite takes a statement out of its cache while it's in use, which the mirror doesn't track.
+pub(crate) struct StatementCache {
+     capacity: Mutex<usize>,
+     recent: Mutex<VecDeque<String>>,
+     hits: AtomicU64,
+     misses: AtomicU64
+}
+
+impl StatementCache {
+     pub(crate) fn new(capacity: usize) -> StatementCache {
+          return StatementCache {
+               capacity: Mutex::new(capacity),
+               recent: Mutex::new(VecDeque::with_capacity(capacity)),
+               hits: AtomicU64::new(0),
+               misses: AtomicU64::new(0)
+          };
+     }
+
+     /// Prepares `query` through the connection's statement cache. Must be called from within `Connection::call`.
+     pub(crate) fn prepare<'conn>(&self, conn: &'conn rusqlite::Connection, query: &str) -> Result<CachedStatement<'conn>, rusqlite::Error> {
+          let statement = conn.prepare_cached(query)?;
+          self.record(query.trim());
+          return Ok(statement);
+     }
+
+     /// Changes the capacity of both the rusqlite cache and the mirror. Must be called from within `Connection::call`.
+     pub(crate) fn set_capacity(&self, conn: &rusqlite::Connection, capacity: usize) {
+          conn.set_prepared_statement_cache_capacity(capacity);
+
+          *self.capacity.lock().unwrap() = capacity;
+          self.recent.lock().unwrap().truncate(capacity);
+     }
+
+     pub(crate) fn stats(&self) -> StatementCacheStats {
+          return StatementCacheStats {
+               hits: self.hits.load(Ordering::Relaxed),
+               misses: self.misses.load(Ordering::Relaxed),
+               capacity: *self.capacity.lock().unwrap()
+          };
+     }
+
+     fn record(&self, query: &str) {
+          let capacity = *self.capacity.lock().unwrap();
+          let mut recent = self.recent.lock().unwrap();
+
+          if let Some(position) = recent.iter().position(|x| x == query) {
+               let query = recent.remove(position).unwrap();
+               recent.push_front(query);
+               self.hits.fetch_add(1, Ordering::Relaxed);
+               return;
+          }
+
+          self.misses.fetch_add(1, Ordering::Relaxed);
+
+          if capacity > 0 {
+               recent.push_front(query.to_string());
+               recent.truncate(capacity);
+          }
+     }
+}
+
+/// INSERT, UPDATE and by id statement texts of a model, generated once from its schema when the database is created.
+pub(crate) struct ModelStatements {
+     pub table_name: String,
+     pub field_names: Vec<String>,
+     /// The primary key column, or `rowid` if the model has none.
+     pub key_column: String,
+     /// Index of the auto increment field, whose 0 value is inserted as NULL so SQLite generates the id.
+     pub auto_increment_index: Option<usize>,
+     pub insert_query: String,
+     /// Sets every field except the primary key, which identifies the row.
+     pub update_query: String,
+     pub get_by_id_query: String,
+     pub update_by_id_query: String,
+     pub delete_by_id_query: String,
+     /// The `#[aio(...)]` attributes of the model, used to map struct fields to columns.
+     pub model_info: Arc<ModelInfo>
+}
+
+impl ModelStatements {
+     pub(crate) fn new(table_name: &str, schema: &[Schema], model_info: Arc<ModelInfo>) -> Result<ModelStatements, AioError> {
+          let field_names: Vec<String> = schema.iter().map(|x| x.field_name.clone()).collect();
+
+          let primary_key = get_primary_key(schema)?;
+          let key_column = primary_key.map(|x| x.field_name.clone()).unwrap_or_else(|| "rowid".into());
+          let auto_increment_index = schema.iter().position(|x| x.auto_increment);
+
+          let non_key_fields: Vec<&String> = field_names.iter().filter(|x| **x != key_column).collect();
+
+          return Ok(ModelStatements {
+               table_name: table_name.to_string(),
+               insert_query: generate_insert_query(table_name, &field_names),
+               update_query: generate_update_query(table_name, &non_key_fields),
+               get_by_id_query: format!("SELECT * FROM {} WHERE {} = ?", table_name, key_column),
+               update_by_id_query: format!("{} WHERE {} = ?", generate_update_query(table_name, &non_key_fields), key_column),
+               delete_by_id_query: format!("DELETE FROM {} WHERE {} = ?", table_name, key_column),
+               field_names: field_names,
+               key_column: key_column,
+               auto_increment_index: auto_increment_index,
+               model_info: model_info
+          });
+     }
+
+     pub(crate) fn column_name<'a>(&'a self, field_name: &'a str) -> Option<&'a str> {
+          return self.model_info.column_name(field_name);
+     }
+
+     /// Leaves out the skipped fields and renames the rest to their columns.
+     pub(crate) fn to_columns<'a>(&self, generic_values: Vec<GenericValue<'a>>) -> Vec<GenericValue<'a>> {
+          return generic_values.into_iter()
+               .filter_map(|mut value| {
+                    value.field_name = self.column_name(&value.field_name)?.to_string();
+                    Some(value)
+               })
+               .collect();
+     }
+
+     /// Returns true if the values were reflected from the same model the statements were generated for.
+     pub(crate) fn matches(&self, generic_values: &[GenericValue]) -> bool {
+          return self.field_names.len() == generic_values.len() &&
+               self.field_names.iter().zip(generic_values.iter()).all(|(name, value)| *name == value.field_name);
+     }
+
+     /// Returns the INSERT statement for the values of a struct, the cached one if they match the model.
+     pub(crate) fn insert_query_for(&self, generic_values: &[GenericValue]) -> Cow<'_, str> {
+          if self.matches(generic_values) {
+               return Cow::Borrowed(&self.insert_query);
+          }
+
+          let field_names: Vec<&str> = generic_values.iter().map(|x| x.field_name.as_str()).collect();
+          return Cow::Owned(generate_insert_query(&self.table_name, &field_names));
+     }
+
+     /// Returns the parameters of the INSERT statement of `insert_query_for` for the values of a struct.
+     pub(crate) fn insert_params(&self, generic_values: &[GenericValue]) -> Result<Vec<Value>, AioError> {
+          let mut params = generic_values.iter()
+               .map(get_sql_value)
+               .collect::<Result<Vec<Value>, AioError>>()?;
+
+          if let Some(index) = self.auto_increment_index.filter(|_| self.matches(generic_values))
+               && params[index] == Value::Integer(0) {
+               params[index] = Value::Null;
+          }
+
+          return Ok(params);
+     }
+
+     /// Returns the `INSERT ... ON CONFLICT` statement of an upsert and the SELECT finding the conflicting row, with their parameters.
+     /// `conflict_columns` and the columns of `action` are already mapped from fields to columns.
+     pub(crate) fn upsert_params(&self, generic_values: &[GenericValue], conflict_columns: &[String], action: &UpsertAction) -> Result<(String, Vec<Value>, String, Vec<Value>), AioError> {
+          if !self.matches(generic_values) {
+               return Err(AioError::SchemaMismatch(format!("The struct provided doesn't match the model of table {}.", self.table_name)));
+          }
+
+          let insert_query = &self.insert_query;
+          let params = self.insert_params(generic_values)?;
+
+          let update_columns: Vec<&String> = match action {
+               UpsertAction::UpdateAll => self.field_names.iter()
+                    .filter(|x| **x != self.key_column && !conflict_columns.contains(x))
+                    .collect(),
+               UpsertAction::Update(columns) => {
+                    if let Some(column) = columns.iter().find(|x| !self.field_names.contains(x)) {
+                         return Err(AioError::SchemaMismatch(format!("{} isn't a column of table {}.", column, self.table_name)));
+                    }
+
+                    columns.iter().collect()
+               },
+               UpsertAction::DoNothing => Vec::new()
+          };
+
+          let conflict_action = if update_columns.is_empty() {
+               "DO NOTHING".to_string()
+          } else {
+               let set_query: Vec<String> = update_columns.iter().map(|x| format!("{} = excluded.{}", x, x)).collect();
+               format!("DO UPDATE SET {}", set_query.join(", "))
+          };
+
+          let upsert_query = format!("{} ON CONFLICT({}) {}", insert_query, conflict_columns.join(","), conflict_action);
+
+          let where_query: Vec<String> = conflict_columns.iter().map(|x| format!("{} = ?", x)).collect();
+          let select_query = format!("SELECT rowid FROM {} WHERE {}", self.table_name, where_query.join(" AND "));
+          let select_params = conflict_columns.iter()
+               .map(|column| self.field_names.iter().position(|x| x == column).map(|index| params[index].clone()))
+               .collect::<Option<Vec<Value>>>()
+               .ok_or_else(|| AioError::SchemaMismatch(format!("The conflict fields {:?} aren't columns of table {}.", conflict_columns, self.table_name)))?;
+
+          return Ok((upsert_query, params, select_query, select_params));
+     }
+
+     /// Returns the UPDATE statement (without its WHERE clause) for the values of a struct and its parameters, leaving out the primary key.
+     pub(crate) fn update_params(&self, generic_values: &[GenericValue]) -> Result<(Cow<'_, str>, Vec<Value>), AioError> {
+          let generic_values: Vec<&GenericValue> = generic_values.iter().filter(|x| x.field_name != self.key_column).collect();
+          let params = generic_values.iter()
+               .map(|x| get_sql_value(x))
+               .collect::<Result<Vec<Value>, AioError>>()?;
+
+          let field_names: Vec<&str> = generic_values.iter().map(|x| x.field_name.as_str()).collect();
+          let non_key_fields = self.field_names.iter().filter(|x| **x != self.key_column);
+
+          if non_key_fields.eq(field_names.iter().copied()) {
+               return Ok((Cow::Borrowed(&self.update_query), params));
+          }
+
+          return Ok((Cow::Owned(generate_update_query(&self.table_name, &field_names)), params));
+     }
+
+     /// Returns the parameters of `update_by_id_query`: the values of every field except the key, followed by the id.
+     pub(crate) fn update_by_id_params(&self, generic_values: &[GenericValue], id: Value) -> Result<Vec<Value>, AioError> {
+          if !self.matches(generic_values) {
+               return Err(AioError::SchemaMismatch(format!("The struct provided doesn't match the model of table {}.", self.table_name)));
+          }
+
+          let mut params = generic_values.iter()
+               .filter(|x| x.field_name != self.key_column)
+               .map(get_sql_value)
+               .collect::<Result<Vec<Value>, AioError>>()?;
+          params.push(id);
+
+          return Ok(params);
+     }
+}
+
+pub(crate) fn generate_insert_query<S: AsRef<str>>(table_name: &str, field_names: &[S]) -> String {
+     let columns: Vec<&str> = field_names.iter().map(|x| x.as_ref()).collect();
+     let placeholders = vec!["?"; columns.len()].join(",");
+     return format!("INSERT INTO {} ({}) VALUES ({})", table_name, columns.join(","), placeholders);
+}
+
+/// Generates `UPDATE table SET a = ?, b = ?`, the WHERE clause is appended by the caller.
+pub(crate) fn generate_update_query<S: AsRef<str>>(table_name: &str, field_names: &[S]) -> String {
+     let set_query: Vec<String> = field_names.iter().map(|x| format!("{} = ?", x.as_ref())).collect();
+     return format!("UPDATE {} SET {}", table_name, set_query.join(", "));
+}
@@ -1,18 +1,175 @@
-use std::fmt;
-
-pub mod aio_database;
-pub(crate) mod internal;
-pub mod models;
-pub mod aio_query;
-
-#[derive(Debug)]
-pub enum WalMode {
-     WAL,
-     //WAL2
-}
-
-impl fmt::Display for WalMode {
-     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-         write!(f, "{:?}", self)
-     }
- }
\ No newline at end of file
+use std::fmt;
+
+pub mod aio_database;
+pub(crate) mod internal;
+pub mod models;
+pub mod aio_query;
+pub mod aio_error;
+pub mod aio_transaction;
+pub mod aio_migration;
+pub mod aio_table;
+pub mod aio_options;
+pub mod aio_pragmas;
+
+#[derive(Debug)]
+pub enum WalMode {
+     WAL,
+     //WAL2
+}
+
+impl fmt::Display for WalMode {
+     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+         write!(f, "{:?}", self)
+     }
+ }
+
+/// The `journal_mode` of the database. File databases default to **Wal**, which lets reads run on the readers while a write is in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JournalMode {
+     Delete,
+     Truncate,
+     Persist,
+     Memory,
+     #[default]
+     Wal,
+     Off
+}
+
+impl fmt::Display for JournalMode {
+     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+          f.write_str(&format!("{:?}", self).to_uppercase())
+     }
+}
+
+impl JournalMode {
+     /// Parses the value returned by `PRAGMA journal_mode`.
+     pub(crate) fn from_pragma(value: &str) -> Option<JournalMode> {
+          return match value.to_lowercase().as_str() {
+               "delete" => Some(JournalMode::Delete),
+               "truncate" => Some(JournalMode::Truncate),
+               "persist" => Some(JournalMode::Persist),
+               "memory" => Some(JournalMode::Memory),
+               "wal" => Some(JournalMode::Wal),
+               "off" => Some(JournalMode::Off),
+               _ => None
+          };
+     }
+}
+
+impl From<WalMode> for JournalMode {
+     fn from(wal_mode: WalMode) -> JournalMode {
+          return match wal_mode {
+               WalMode::WAL => JournalMode::Wal
+          };
+     }
+}
+
+/// The `synchronous` level of the connection. Defaults to **Normal**, safe from corruption in WAL mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Synchronous {
+     Off,
+     #[default]
+     Normal,
+     Full,
+     Extra
+}
+
+impl fmt::Display for Synchronous {
+     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+          f.write_str(&format!("{:?}", self).to_uppercase())
+     }
+}
+
+impl Synchronous {
+     /// Parses the value returned by `PRAGMA synchronous`.
+     pub(crate) fn from_pragma(value: i64) -> Option<Synchronous> {
+          return match value {
+               0 => Some(Synchronous::Off),
+               1 => Some(Synchronous::Normal),
+               2 => Some(Synchronous::Full),
+               3 => Some(Synchronous::Extra),
+               _ => None
+          };
+     }
+}
+
+/// The `auto_vacuum` mode of the database, only applied when the database file is created. Defaults to **Full**.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutoVacuum {
+     None,
+     #[default]
+     Full,
+     Incremental
+}
+
+impl fmt::Display for AutoVacuum {
+     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+          f.write_str(&format!("{:?}", self).to_uppercase())
+     }
+}
+
+impl AutoVacuum {
+     /// Parses the value returned by `PRAGMA auto_vacuum`.
+     pub(crate) fn from_pragma(value: i64) -> Option<AutoVacuum> {
+          return match value {
+               0 => Some(AutoVacuum::None),
+               1 => Some(AutoVacuum::Full),
+               2 => Some(AutoVacuum::Incremental),
+               _ => None
+          };
+     }
+}
+
+/// Where temporary tables and indexes are stored, `PRAGMA temp_store`. Defaults to **Memory**.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TempStore {
+     /// The compile time default of SQLite, usually a file.
+     Default,
+     File,
+     #[default]
+     Memory
+}
+
+impl fmt::Display for TempStore {
+     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+          f.write_str(&format!("{:?}", self).to_uppercase())
+     }
+}
+
+impl TempStore {
+     /// Parses the value returned by `PRAGMA temp_store`.
+     pub(crate) fn from_pragma(value: i64) -> Option<TempStore> {
+          return match value {
+               0 => Some(TempStore::Default),
+               1 => Some(TempStore::File),
+               2 => Some(TempStore::Memory),
+               _ => None
+          };
+     }
+}
+
+/// `PRAGMA locking_mode`. **Exclusive** keeps the locks of the writer until the database is closed,
+/// so the reader pool isn't opened. Defaults to **Normal**.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockingMode {
+     #[default]
+     Normal,
+     Exclusive
+}
+
+impl fmt::Display for LockingMode {
+     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+          f.write_str(&format!("{:?}", self).to_uppercase())
+     }
+}
+
+impl LockingMode {
+     /// Parses the value returned by `PRAGMA locking_mode`.
+     pub(crate) fn from_pragma(value: &str) -> Option<LockingMode> {
+          return match value.to_lowercase().as_str() {
+               "normal" => Some(LockingMode::Normal),
+               "exclusive" => Some(LockingMode::Exclusive),
+               _ => None
+          };
+     }
+}
\ No newline at end of file
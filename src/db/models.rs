@@ -1,14 +1,422 @@
-use bevy_reflect::PartialReflect;
-
-#[derive(Default, Clone, Debug)]
-pub struct Schema {
-     pub field_name: String,
-     pub field_type: String
-}
-
-#[derive(Debug)]
-pub struct GenericValue<'a> {
-     pub field_name: String,
-     pub field_value: &'a dyn PartialReflect,
-     pub field_type: String
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant};
+
+use bevy_reflect::PartialReflect;
+use rusqlite::types::Value;
+
+use super::aio_error::AioError;
+use super::{AutoVacuum, JournalMode, LockingMode, Synchronous, TempStore};
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct Schema {
+     pub field_name: String,
+     /// The type of the field, for `Option<X>` fields the type of `X`.
+     pub field_type: String,
+     /// The field is an `Option<X>`, stored in a column that may contain NULL.
+     pub nullable: bool,
+     pub primary_key: bool,
+     pub auto_increment: bool,
+     /// A unique index is created for the column.
+     pub unique: bool,
+     /// A non-unique index is created for the column.
+     pub index: bool,
+     /// SQL literal used as the `DEFAULT` of the column.
+     pub default: Option<String>,
+     /// Former names of the column, an existing column with one of these names is renamed instead of adding a new one.
+     pub renamed_from: Vec<String>,
+     /// Hidden column of a virtual table, only set for columns read from the database.
+     pub hidden: bool,
+     /// Generated column (`GENERATED ALWAYS AS`), only set for columns read from the database.
+     pub generated: bool
+}
+
+/// Table and column metadata of a model, implemented with `#[derive(AioModel)]`.
+/// ```rust
+/// #[derive(Default, Clone, Debug, Reflect, AioModel)]
+/// #[aio(table = "people")]
+/// struct Person {
+///     #[aio(primary_key)]
+///     email: String,
+///     #[aio(rename = "full_name", index)]
+///     name: String,
+///     #[aio(default = 18)]
+///     age: u32,
+///     #[aio(skip)]
+///     cached_score: u64,
+///     #[aio(renamed_from = "town")]
+///     city: String
+/// }
+/// ```
+pub trait AioModel {
+     fn model_info() -> ModelInfo;
+}
+
+/// Returned by `AioModel::model_info`.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct ModelInfo {
+     /// Overrides the table name passed when creating the database.
+     pub table_name: Option<String>,
+     /// The version of the model, set with `#[aio(version = N)]`. Tables without a recorded version are at version 0.
+     pub version: u32,
+     pub fields: Vec<FieldInfo>
+}
+
+/// The `#[aio(...)]` attributes of a struct field.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct FieldInfo {
+     pub field_name: String,
+     /// The column the field is stored in, if different from the field name.
+     pub column_name: Option<String>,
+     /// Set with `#[aio(primary_key)]`, several fields make a composite key.
+     pub primary_key: bool,
+     /// Set with `#[aio(auto_increment)]` on an integer field, an `INTEGER PRIMARY KEY AUTOINCREMENT` column. When inserting, a value of 0 lets SQLite generate the id.
+     pub auto_increment: bool,
+     pub unique: bool,
+     pub index: bool,
+     /// The field isn't stored, it's left to its default value when read.
+     pub skip: bool,
+     /// SQL literal used as the `DEFAULT` of the column.
+     pub default: Option<String>,
+     /// Former column names of the field, set with `#[aio(renamed_from = "...")]`.
+     pub renamed_from: Vec<String>
+}
+
+impl ModelInfo {
+     pub fn field(&self, field_name: &str) -> Option<&FieldInfo> {
+          return self.fields.iter().find(|x| x.field_name == field_name);
+     }
+
+     /// Returns the column a struct field is stored in, or None if the field is skipped. Fields unknown to the model keep their name.
+     pub(crate) fn column_name<'a>(&'a self, field_name: &'a str) -> Option<&'a str> {
+          return match self.field(field_name) {
+               Some(field_info) if field_info.skip => None,
+               Some(field_info) => Some(field_info.column_name.as_deref().unwrap_or(field_name)),
+               None => Some(field_name)
+          };
+     }
+}
+
+#[derive(Debug)]
+pub struct GenericValue<'a> {
+     pub field_name: String,
+     pub field_value: &'a dyn PartialReflect,
+     pub field_type: String,
+     pub nullable: bool
+}
+
+/// A generated SQL fragment together with the values bound to its `?` placeholders, in order.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct BoundQuery {
+     pub query: String,
+     pub params: Vec<Value>
+}
+
+/// Hit / miss counters of the prepared statement cache, returned by `AioDatabase::statement_cache_stats`.
+/// `hits` and `misses` are approximate, counted by mirroring the SQL texts of rusqlite's cache, and may drift from it
+/// (e.g. a statement that is still in use when it's prepared again is counted as a hit but prepared anew).
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatementCacheStats {
+     pub hits: u64,
+     pub misses: u64,
+     pub capacity: usize
+}
+
+/// Size and wait counters of the connections, returned by `AioDatabase::pool_stats`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolStats {
+     /// Read-only connections, 0 if reads run on the writer (in-memory databases, journal modes other than WAL).
+     pub readers: usize,
+     /// Readers not running a query.
+     pub idle_readers: usize,
+     /// Reads that ran on a reader.
+     pub reader_acquisitions: u64,
+     /// How long reads waited for an idle reader, in total.
+     pub reader_wait: Duration,
+     /// The longest wait for an idle reader.
+     pub max_reader_wait: Duration,
+     /// Calls and transactions that reserved the writer.
+     pub writer_acquisitions: u64,
+     /// How long calls and transactions waited for the writer, in total.
+     pub writer_wait: Duration,
+     /// The longest wait for the writer.
+     pub max_writer_wait: Duration
+}
+
+/// The settings of the database and its writer connection, returned by `AioDatabase::settings_snapshot`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DatabaseSettings {
+     pub journal_mode: JournalMode,
+     pub synchronous: Synchronous,
+     pub locking_mode: LockingMode,
+     pub auto_vacuum: AutoVacuum,
+     pub temp_store: TempStore,
+     /// In bytes.
+     pub page_size: u32,
+     /// Pages if positive, KiB if negative.
+     pub cache_size: i64,
+     /// In bytes, 0 if memory mapped I/O is disabled.
+     pub mmap_size: u64,
+     pub busy_timeout: Duration,
+     pub foreign_keys: bool,
+     /// In pages, 0 if automatic checkpoints are disabled.
+     pub wal_autocheckpoint: u32,
+     /// In bytes, -1 for no limit.
+     pub journal_size_limit: i64
+}
+
+/// How insert, update and delete calls are retried when the database is busy or locked (`SQLITE_BUSY` / `SQLITE_LOCKED`).
+/// Every other error is returned immediately. The wait before the next attempt starts at `initial_backoff` and doubles up to `max_backoff`.
+///
+/// SQLite can also wait for the lock itself with `busy_timeout` (`AioDatabaseOptions::busy_timeout`, `Pragmas::set_busy_timeout`).
+/// Every attempt first waits up to the busy_timeout, so a busy write returns `AioError::Busy` after about `max_attempts` times the
+/// busy_timeout plus the backoffs. A database opened with a busy_timeout (5 seconds by default) therefore uses `RetryPolicy::none()`,
+/// and `RetryPolicy::default()` if the busy_timeout is 0. Set a short busy_timeout when retrying with a policy.
+/// ### Example
+/// ```rust
+/// db.set_retry_policy(RetryPolicy::new(10)
+///     .backoff(Duration::from_millis(5), Duration::from_millis(500))
+///     .deadline(Duration::from_secs(2)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+     /// Attempts including the first one, 0 is the same as 1.
+     pub max_attempts: u32,
+     pub initial_backoff: Duration,
+     pub max_backoff: Duration,
+     /// Waits a random duration between half and all of the backoff, so callers that were busy together don't retry together.
+     pub jitter: bool,
+     /// Gives up (returning the last error) when the next wait would end after this much time since the first attempt.
+     pub deadline: Option<Duration>
+}
+
+impl Default for RetryPolicy {
+     /// 5 attempts, waiting from 10ms up to 1s with jitter, no deadline.
+     fn default() -> RetryPolicy {
+          return RetryPolicy {
+               max_attempts: 5,
+               initial_backoff: Duration::from_millis(10),
+               max_backoff: Duration::from_secs(1),
+               jitter: true,
+               deadline: None
+          };
+     }
+}
+
+impl RetryPolicy {
+     /// The default policy with `max_attempts`.
+     pub fn new(max_attempts: u32) -> RetryPolicy {
+          return RetryPolicy {
+               max_attempts: max_attempts,
+               ..Default::default()
+          };
+     }
+
+     /// A single attempt, busy errors are returned once SQLite's `busy_timeout` has elapsed.
+     pub fn none() -> RetryPolicy {
+          return RetryPolicy::new(1);
+     }
+
+     pub fn backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> RetryPolicy {
+          self.initial_backoff = initial_backoff;
+          self.max_backoff = max_backoff;
+          return self;
+     }
+
+     pub fn jitter(mut self, jitter: bool) -> RetryPolicy {
+          self.jitter = jitter;
+          return self;
+     }
+
+     pub fn deadline(mut self, deadline: Duration) -> RetryPolicy {
+          self.deadline = Some(deadline);
+          return self;
+     }
+
+     /// The policy of a database opened with `busy_timeout`: `none()` if SQLite already waits for the lock, `default()` otherwise.
+     pub(crate) fn for_busy_timeout(busy_timeout: Duration) -> RetryPolicy {
+          return match busy_timeout.is_zero() {
+               true => RetryPolicy::default(),
+               false => RetryPolicy::none()
+          };
+     }
+
+     /// The wait after the failed `attempt` (starting at 1).
+     pub(crate) fn wait_after(&self, attempt: u32) -> Duration {
+          let backoff = self.initial_backoff
+               .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+               .min(self.max_backoff);
+
+          if !self.jitter {
+               return backoff;
+          }
+
+          let random = RandomState::new().hash_one(Instant::now()) as f64 / u64::MAX as f64;
+          return backoff.mul_f64(0.5 + random / 2.0);
+     }
+}
+
+/// What `insert_many` / `insert_iter` do when a row can't be inserted.
+/// - **AllOrNothing** = stop and roll back the whole batch, returning the error
+/// - **SkipFailed** = keep inserting the remaining rows and report the failed ones in `InsertManyResult::failed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BulkInsertMode {
+     #[default]
+     AllOrNothing,
+     SkipFailed
+}
+
+/// Returned by `insert_many` / `insert_iter`.
+#[derive(Debug, Default)]
+pub struct InsertManyResult {
+     /// How many rows were inserted.
+     pub inserted: usize,
+     /// The index of every row that couldn't be inserted, with its error. Always empty with `BulkInsertMode::AllOrNothing`.
+     pub failed: Vec<(usize, AioError)>
+}
+
+/// What `upsert_with` does when the value conflicts with an existing row on the conflict fields.
+/// - **UpdateAll** = update every field except the conflict fields and the primary key
+/// - **Update** = update only the given fields
+/// - **DoNothing** = keep the existing row as it is
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum UpsertAction {
+     #[default]
+     UpdateAll,
+     Update(Vec<String>),
+     DoNothing
+}
+
+/// Returned by `upsert` / `upsert_with`, with the rowid of the row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertResult {
+     /// No row had the same conflict fields, the value was inserted.
+     Inserted(i64),
+     /// The existing row was updated.
+     Updated(i64),
+     /// The existing row was kept, with `UpsertAction::DoNothing` (or `Update` with no field).
+     Unchanged(i64)
+}
+
+impl UpsertResult {
+     pub fn rowid(&self) -> i64 {
+          return match self {
+               UpsertResult::Inserted(rowid) | UpsertResult::Updated(rowid) | UpsertResult::Unchanged(rowid) => *rowid
+          };
+     }
+
+     pub fn is_inserted(&self) -> bool {
+          return matches!(self, UpsertResult::Inserted(_));
+     }
+}
+
+/// What `create_with_policy` / `create_in_memory_with_policy` do when the existing table doesn't match the model.
+/// - **AdditiveOnly** = add the new columns, keep the columns that were removed from the model
+/// - **AllowDrops** = add the new columns and drop the columns that were removed from the model
+/// - **Fail** = return `AioError::MigrationRequired` instead of changing the table
+/// - **DryRun** = only compute the `MigrationReport`, the database file is opened read only and isn't changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MigrationPolicy {
+     #[default]
+     AdditiveOnly,
+     AllowDrops,
+     Fail,
+     DryRun
+}
+
+/// The changes between the model and the existing table, returned when the database is created.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MigrationReport {
+     pub table_name: String,
+     /// The table didn't exist and was created (or would be, with `MigrationPolicy::DryRun`).
+     pub created: bool,
+     /// Columns of new fields.
+     pub added: Vec<Schema>,
+     /// Columns renamed with `ALTER TABLE ... RENAME COLUMN` because of `#[aio(renamed_from = "...")]`.
+     pub renamed: Vec<ColumnRename>,
+     /// A removed and an added column of the same type, which might be a rename that wasn't declared. Only reported, not applied.
+     pub possible_renames: Vec<ColumnRename>,
+     /// New columns filled by a `Migrator::backfill` callback.
+     pub backfilled: Vec<String>,
+     /// Columns of removed fields that were dropped (or would be, with `MigrationPolicy::DryRun`).
+     pub dropped: Vec<String>,
+     /// Columns of removed fields that were kept by `MigrationPolicy::AdditiveOnly`.
+     pub kept: Vec<String>,
+     /// Columns whose SQL type differs from the type of their field, converted by rebuilding the table.
+     pub changed: Vec<ColumnChange>,
+     /// The changes were applied to the database, false with `MigrationPolicy::DryRun`.
+     pub applied: bool,
+     /// Model versions the table was upgraded to with `Migrator::upgrade` (or would be, with `MigrationPolicy::DryRun`), in order.
+     pub upgraded_versions: Vec<u32>,
+     /// Versions of the `Migrator` steps that were run, in order.
+     pub applied_versions: Vec<u32>,
+     /// Versions of the `Migrator` steps that weren't run yet, only set with `MigrationPolicy::DryRun`.
+     pub pending_versions: Vec<u32>
+}
+
+impl MigrationReport {
+     /// Returns true if an existing table doesn't match the model.
+     pub fn has_changes(&self) -> bool {
+          return !self.added.is_empty() || !self.renamed.is_empty() || !self.dropped.is_empty() || !self.kept.is_empty() || !self.changed.is_empty();
+     }
+}
+
+/// A column whose SQL type differs from the type of its field.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnChange {
+     pub column_name: String,
+     /// The SQL type of the column in the database.
+     pub from_type: String,
+     /// The SQL type of the field.
+     pub to_type: String
+}
+
+/// A column renamed from `from` to `to`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnRename {
+     pub from: String,
+     pub to: String
+}
+
+/// The live schema of a table, read with `PRAGMA table_xinfo`, `index_list` and `foreign_key_list`. Returned by `AioDatabase::describe`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableDescription {
+     pub table_name: String,
+     /// The columns, `field_type` is the declared SQL type (e.g. `UNSIGNED BIG INT`).
+     pub columns: Vec<Schema>,
+     pub indexes: Vec<IndexInfo>,
+     pub foreign_keys: Vec<ForeignKeyInfo>
+}
+
+/// An index of a table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexInfo {
+     pub name: String,
+     pub columns: Vec<String>,
+     pub unique: bool,
+     /// `c` for `CREATE INDEX`, `u` for a `UNIQUE` constraint and `pk` for a `PRIMARY KEY` constraint.
+     pub origin: String
+}
+
+/// A foreign key of a table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ForeignKeyInfo {
+     pub column_name: String,
+     pub foreign_table: String,
+     /// The referenced column, None if it's the primary key of the foreign table.
+     pub foreign_column: Option<String>,
+     pub on_update: String,
+     pub on_delete: String
+}
+
+/// A versioned migration step recorded in the `_aio_migrations` table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppliedMigration {
+     /// The table of the model the step was registered for, every model has its own versions.
+     pub table_name: String,
+     pub version: u32,
+     pub name: String,
+     pub checksum: String,
+     /// Unix timestamp in seconds.
+     pub applied_at: i64
 }
\ No newline at end of file